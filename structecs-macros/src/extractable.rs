@@ -1,72 +1,172 @@
-use std::{collections::HashMap, iter::chain};
-
 use proc_macro2::TokenStream;
-use syn::{DeriveInput, Ident, Type, punctuated::Punctuated};
+use syn::{
+    DeriveInput, Ident, Type,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+};
 
 enum Metadata<'a> {
-    Offset0 {
-        target_type: &'a Ident,
-    },
+    Offset0,
     Nested {
-        field_ident: &'a Ident,
+        member: syn::Member,
+        target_type: &'a Type,
+    },
+    Leaf {
+        member: syn::Member,
+        target_type: &'a Type,
+    },
+    Field {
+        member: syn::Member,
+        target_type: &'a Type,
+    },
+    /// A field of an enum variant, extracted with `mode`.
+    Variant {
+        variant_ident: &'a Ident,
+        member: syn::Member,
         target_type: &'a Type,
+        mode: FieldMode,
     },
 }
 
-pub(crate) fn internal_derive(input: DeriveInput) -> syn::Result<TokenStream> {
-    let target_fields: Vec<Ident> = input.attrs.iter().try_fold(Vec::new(), |mut acc, attr| {
-        if !attr.path().is_ident("extractable") {
-            return Ok::<_, syn::Error>(acc);
-        }
-        let target_fields: Punctuated<Ident, syn::Token![,]> =
-            attr.parse_args_with(Punctuated::parse_terminated)?;
-        acc.extend(target_fields);
-        Ok(acc)
-    })?;
-
-    let offset0 = Metadata::Offset0 {
-        target_type: &input.ident,
-    };
+/// How a single field takes part in extraction.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FieldMode {
+    /// `#[extractable]`: only the field's own `Extractable` type.
+    Leaf,
+    /// `#[extractable(flatten)]` or a struct-level listing: the field's type and
+    /// every type nested inside it.
+    Flatten,
+    /// `#[extractable(skip)]`: never extractable.
+    Skip,
+    /// `#[extract]`: the field's own type, which does not need to be `Extractable`.
+    Extract,
+}
 
-    let data_struct = match &input.data {
-        _ if target_fields.is_empty() => {
-            return expand(vec![offset0], &input);
+/// An item of the struct-level `#[extractable(...)]` list.
+enum StructItem {
+    /// A field to flatten into the extraction metadata, by name or tuple index.
+    Field(syn::Member),
+    /// `allow_duplicates = <bool>`
+    AllowDuplicates(syn::LitBool),
+    /// `tag = "<name>"`, registering the type for tagged serialization.
+    Tag(syn::LitStr),
+    /// `on_destroy = <path>`, a `fn(&mut Self)` called before an entity is dropped.
+    OnDestroy(syn::Path),
+    /// `dyn <Trait>`, a trait object the type can be extracted as.
+    Dyn(syn::TypeTraitObject),
+}
+
+impl Parse for StructItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::LitInt) {
+            return Ok(StructItem::Field(syn::Member::Unnamed(input.parse()?)));
         }
-        syn::Data::Struct(data) => data,
-        _ => {
-            return Err(syn::Error::new_spanned(
-                &input.ident,
-                "Extractable fields can only be derived for structs.",
-            ));
+        if input.peek(syn::Token![dyn]) {
+            return Ok(StructItem::Dyn(input.parse()?));
         }
-    };
+        let ident: Ident = input.parse()?;
+        if !input.peek(syn::Token![=]) {
+            return Ok(StructItem::Field(syn::Member::Named(ident)));
+        }
+        input.parse::<syn::Token![=]>()?;
+        if ident == "allow_duplicates" {
+            Ok(StructItem::AllowDuplicates(input.parse()?))
+        } else if ident == "tag" {
+            Ok(StructItem::Tag(input.parse()?))
+        } else if ident == "on_destroy" {
+            Ok(StructItem::OnDestroy(input.parse()?))
+        } else {
+            Err(syn::Error::new_spanned(
+                &ident,
+                format!(
+                    "Unknown option '{}'. Expected 'allow_duplicates', 'tag' or 'on_destroy'.",
+                    ident
+                ),
+            ))
+        }
+    }
+}
 
-    let fields = match data_struct.fields {
-        syn::Fields::Named(ref fields_named) => &fields_named.named,
-        _ => {
-            return Err(syn::Error::new_spanned(
-                &input.ident,
-                "Extractable can only be derived for structs with named fields. Cannot be derived for tuple structs or unit structs.",
-            ));
+/// Options set by the struct-level `#[extractable(...)]` attributes.
+#[derive(Default)]
+struct StructOptions {
+    target_fields: Vec<syn::Member>,
+    allow_duplicates: bool,
+    tag: Option<syn::LitStr>,
+    on_destroy: Option<syn::Path>,
+    dyn_traits: Vec<syn::TypeTraitObject>,
+}
+
+impl StructOptions {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("extractable"))
+        {
+            let items: Punctuated<StructItem, syn::Token![,]> =
+                attr.parse_args_with(Punctuated::parse_terminated)?;
+            for item in items {
+                match item {
+                    StructItem::Field(member) => options.target_fields.push(member),
+                    StructItem::AllowDuplicates(value) => options.allow_duplicates = value.value,
+                    StructItem::Tag(tag) => {
+                        if options.tag.is_some() {
+                            return Err(syn::Error::new_spanned(tag, "Duplicate 'tag' option."));
+                        }
+                        options.tag = Some(tag);
+                    }
+                    StructItem::OnDestroy(path) => {
+                        if options.on_destroy.is_some() {
+                            return Err(syn::Error::new_spanned(
+                                path,
+                                "Duplicate 'on_destroy' option.",
+                            ));
+                        }
+                        options.on_destroy = Some(path);
+                    }
+                    StructItem::Dyn(trait_object) => options.dyn_traits.push(trait_object),
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+pub(crate) fn internal_derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let options = StructOptions::parse(&input.attrs)?;
+    let target_fields = &options.target_fields;
+
+    let fields = match &input.data {
+        syn::Data::Struct(data) => &data.fields,
+        syn::Data::Enum(data) => return internal_derive_enum(data, &options, &input),
+        syn::Data::Union(data) => {
+            reject_field_attrs(&data.fields.named)?;
+            return expand_without_fields(&options, &input);
         }
     };
 
-    let field_idents: HashMap<&Ident, &Type> = fields
+    let mut modes = fields
         .iter()
-        .filter_map(|field| {
-            let field_ident = field.ident.as_ref()?;
-            let field_type = &field.ty;
-            Some((field_ident, field_type))
-        })
-        .collect();
+        .map(field_mode)
+        .collect::<syn::Result<Vec<Option<FieldMode>>>>()?;
 
-    let attrs = target_fields
-        .iter()
-        .map(|field_ident| {
-            let target_type = field_idents.get(field_ident).ok_or_else(|| {
-                let available_fields: Vec<String> = field_idents
-                    .keys()
-                    .map(|ident| format!("'{}'", ident))
+    if target_fields.is_empty() && modes.iter().all(Option::is_none) {
+        return expand(vec![Metadata::Offset0], &options, &input);
+    }
+
+    let members: Vec<syn::Member> = fields.members().collect();
+    let mut listed = vec![false; modes.len()];
+    for target in target_fields {
+        let name = member_name(target);
+        let index = members
+            .iter()
+            .position(|member| member == target)
+            .ok_or_else(|| {
+                let available_fields: Vec<String> = members
+                    .iter()
+                    .map(|member| format!("'{}'", member_name(member)))
                     .collect();
                 let suggestion = if available_fields.is_empty() {
                     String::from("This struct has no fields.")
@@ -74,60 +174,367 @@ pub(crate) fn internal_derive(input: DeriveInput) -> syn::Result<TokenStream> {
                     format!("Available fields: {}", available_fields.join(", "))
                 };
                 syn::Error::new_spanned(
-                    field_ident,
-                    format!(
-                        "Field '{}' not found in struct. {}",
-                        field_ident, suggestion
-                    ),
+                    target,
+                    format!("Field '{}' not found in struct. {}", name, suggestion),
                 )
             })?;
 
-            Ok(Metadata::Nested {
-                field_ident,
-                target_type,
-            })
-        })
-        .collect::<Result<Vec<Metadata>, syn::Error>>()?;
+        let message = match modes[index] {
+            None => {
+                modes[index] = Some(FieldMode::Flatten);
+                listed[index] = true;
+                continue;
+            }
+            _ if listed[index] => format!("Field '{}' is listed more than once.", name),
+            Some(FieldMode::Skip) => format!(
+                "Field '{}' is marked #[extractable(skip)] and cannot be listed here.",
+                name
+            ),
+            Some(_) => format!(
+                "Field '{}' already has a field-level extraction attribute. Remove it from this list.",
+                name
+            ),
+        };
+        return Err(syn::Error::new_spanned(target, message));
+    }
+
+    let attrs = fields
+        .iter()
+        .zip(members)
+        .zip(modes)
+        .filter_map(|((field, member), mode)| {
+            let target_type = &field.ty;
+            match mode? {
+                FieldMode::Skip => None,
+                FieldMode::Leaf => Some(Metadata::Leaf {
+                    member,
+                    target_type,
+                }),
+                FieldMode::Flatten => Some(Metadata::Nested {
+                    member,
+                    target_type,
+                }),
+                FieldMode::Extract => Some(Metadata::Field {
+                    member,
+                    target_type,
+                }),
+            }
+        });
+
+    expand(
+        std::iter::once(Metadata::Offset0).chain(attrs).collect(),
+        &options,
+        &input,
+    )
+}
+
+/// The name of a field as written in source, e.g. `health` or `0`.
+fn member_name(member: &syn::Member) -> String {
+    match member {
+        syn::Member::Named(ident) => ident.to_string(),
+        syn::Member::Unnamed(index) => index.index.to_string(),
+    }
+}
+
+/// Parse the extraction attributes of a single field.
+fn field_mode(field: &syn::Field) -> syn::Result<Option<FieldMode>> {
+    let mut mode = None;
+    for attr in &field.attrs {
+        let parsed = if attr.path().is_ident("extract") {
+            attr.meta.require_path_only()?;
+            FieldMode::Extract
+        } else if attr.path().is_ident("extractable") {
+            match &attr.meta {
+                syn::Meta::Path(_) => FieldMode::Leaf,
+                _ => {
+                    let option: Ident = attr.parse_args()?;
+                    if option == "flatten" {
+                        FieldMode::Flatten
+                    } else if option == "skip" {
+                        FieldMode::Skip
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            &option,
+                            format!(
+                                "Unknown field option '{}'. Expected 'flatten' or 'skip'.",
+                                option
+                            ),
+                        ));
+                    }
+                }
+            }
+        } else {
+            continue;
+        };
+
+        if mode.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "A field can only have one extraction attribute.",
+            ));
+        }
+        mode = Some(parsed);
+    }
+    Ok(mode)
+}
+
+/// Derive for enums, whose variant fields are only extractable while the value holds
+/// the variant.
+fn internal_derive_enum(
+    data: &syn::DataEnum,
+    options: &StructOptions,
+    input: &DeriveInput,
+) -> syn::Result<TokenStream> {
+    if let Some(target) = options.target_fields.first() {
+        return Err(syn::Error::new_spanned(
+            target,
+            "Enums cannot list fields here. Mark the variant fields with #[extractable], #[extractable(flatten)] or #[extract] instead.",
+        ));
+    }
+
+    let mut attrs = vec![Metadata::Offset0];
+    for variant in &data.variants {
+        for (field, member) in variant.fields.iter().zip(variant.fields.members()) {
+            let Some(mode) = field_mode(field)? else {
+                continue;
+            };
+            if mode == FieldMode::Skip {
+                continue;
+            }
+            attrs.push(Metadata::Variant {
+                variant_ident: &variant.ident,
+                member,
+                target_type: &field.ty,
+                mode,
+            });
+        }
+    }
+    expand(attrs, options, input)
+}
+
+/// Reject extraction attributes on fields of unions.
+fn reject_field_attrs<'a>(fields: impl IntoIterator<Item = &'a syn::Field>) -> syn::Result<()> {
+    for field in fields {
+        if let Some(attr) = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("extractable") || attr.path().is_ident("extract"))
+        {
+            return Err(syn::Error::new(
+                attr.span(),
+                "Field-level extraction attributes are only supported on struct and enum fields.",
+            ));
+        }
+    }
+    Ok(())
+}
 
-    expand(chain([offset0], attrs).collect(), &input)
+fn expand_without_fields(options: &StructOptions, input: &DeriveInput) -> syn::Result<TokenStream> {
+    if !options.target_fields.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Extractable fields can only be derived for structs and enums.",
+        ));
+    }
+    expand(vec![Metadata::Offset0], options, input)
 }
 
-fn expand(attr: Vec<Metadata<'_>>, input: &DeriveInput) -> syn::Result<TokenStream> {
+fn expand(
+    attr: Vec<Metadata<'_>>,
+    options: &StructOptions,
+    input: &DeriveInput,
+) -> syn::Result<TokenStream> {
     let struct_name = &input.ident;
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // `Extractable: 'static`, so every type parameter has to outlive 'static.
+    let mut generics = input.generics.clone();
+    let type_params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = generics.make_where_clause();
+    for param in type_params {
+        where_clause
+            .predicates
+            .push(syn::parse_quote!(#param: 'static));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let metadata_list = attr
         .iter()
         .map(|attr| match attr {
-            Metadata::Offset0 { target_type } => {
+            Metadata::Offset0 => {
                 quote::quote! {
-                    structecs::ExtractionMetadata::new::<#target_type>(0),
+                    structecs::ExtractionMetadata::new::<Self>(0),
                 }
             }
             Metadata::Nested {
-                field_ident,
+                member,
                 target_type,
             } => {
+                let field = member_name(member);
                 quote::quote! {
                     structecs::ExtractionMetadata::new_nested::<#target_type>(
-                        core::mem::offset_of!(#struct_name, #field_ident),
-                        #target_type::METADATA_LIST,
-                    ),
+                        core::mem::offset_of!(Self, #member),
+                        <#target_type as structecs::Extractable>::METADATA_LIST,
+                    )
+                    .with_field(#field),
+                }
+            }
+            Metadata::Leaf {
+                member,
+                target_type,
+            } => {
+                let field = member_name(member);
+                quote::quote! {
+                    structecs::ExtractionMetadata::new::<#target_type>(
+                        core::mem::offset_of!(Self, #member),
+                    )
+                    .with_field(#field),
+                }
+            }
+            Metadata::Field {
+                member,
+                target_type,
+            } => {
+                let field = member_name(member);
+                quote::quote! {
+                    structecs::ExtractionMetadata::new_field::<#target_type>(
+                        core::mem::offset_of!(Self, #member),
+                        stringify!(#target_type),
+                    )
+                    .with_field(#field),
+                }
+            }
+            Metadata::Variant {
+                variant_ident,
+                member,
+                target_type,
+                mode,
+            } => {
+                // Enums have no stable `offset_of!`, so the field is located by matching
+                // on the value each time it is extracted.
+                let locate = quote::quote! {
+                    |data: core::ptr::NonNull<u8>| {
+                        // SAFETY: The extractor only passes pointers to a live `Self`.
+                        match unsafe { data.cast::<Self>().as_ref() } {
+                            Self::#variant_ident { #member: field, .. } => {
+                                Some(core::ptr::NonNull::from(field).cast::<u8>())
+                            }
+                            #[allow(unreachable_patterns)]
+                            _ => None,
+                        }
+                    }
+                };
+                let field = format!("{}.{}", variant_ident, member_name(member));
+                match mode {
+                    FieldMode::Extract => quote::quote! {
+                        structecs::ExtractionMetadata::new_variant_field::<#target_type>(
+                            #locate,
+                            stringify!(#target_type),
+                        )
+                        .with_field(#field),
+                    },
+                    FieldMode::Flatten => quote::quote! {
+                        structecs::ExtractionMetadata::new_variant::<#target_type>(
+                            #locate,
+                            <#target_type as structecs::Extractable>::METADATA_LIST,
+                        )
+                        .with_field(#field),
+                    },
+                    _ => quote::quote! {
+                        structecs::ExtractionMetadata::new_variant::<#target_type>(
+                            #locate,
+                            &[],
+                        )
+                        .with_field(#field),
+                    },
                 }
             }
         })
         .collect::<TokenStream>();
 
+    // Inventory entries are static, so a generic struct cannot be registered for
+    // every instantiation. Those are resolved lazily on first use instead.
+    let registered = input.generics.params.is_empty();
+    let allow_duplicates = options.allow_duplicates;
+    let registration = if registered {
+        quote::quote! {
+            structecs::__private::submit! {
+                structecs::ExtractableType::new::<#struct_name>()
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+    let serde_registration = match &options.tag {
+        Some(tag) if !registered => {
+            return Err(syn::Error::new_spanned(
+                tag,
+                "'tag' cannot be used on generic types. Submit a `SerdeType` for each instantiation with `inventory::submit!` instead.",
+            ));
+        }
+        Some(tag) => quote::quote! {
+            structecs::__private::submit! {
+                structecs::SerdeType::new::<#struct_name>(#tag)
+            }
+        },
+        None => TokenStream::new(),
+    };
+
+    let on_destroy = options.on_destroy.as_ref().map(|path| {
+        quote::quote_spanned! {path.span()=>
+            const ON_DESTROY: Option<fn(&mut Self)> = Some(#path);
+        }
+    });
+
+    let dyn_metadata_list = options.dyn_traits.iter().map(|trait_object| {
+        quote::quote_spanned! {trait_object.span()=>
+            // SAFETY: The cast only unsizes the pointer to `Self`.
+            unsafe {
+                structecs::DynMetadata::new::<#trait_object>(
+                    |data: core::ptr::NonNull<u8>| -> core::ptr::NonNull<#trait_object> {
+                        data.cast::<Self>()
+                    },
+                )
+            },
+        }
+    });
+    let dyn_metadata = (!options.dyn_traits.is_empty()).then(|| {
+        quote::quote! {
+            const DYN_METADATA_LIST: &'static [structecs::DynMetadata] = &[
+                #(#dyn_metadata_list)*
+            ];
+        }
+    });
+
+    let contains = expand_contains(&attr, &generics, struct_name);
+
+    // Instantiations of a generic type share one identifier, so it is marked to keep
+    // the compile-time duplicate check from mistaking them for the same type.
+    let struct_name_str = if registered {
+        struct_name.to_string()
+    } else {
+        format!("{struct_name}<>")
+    };
+
     Ok(quote::quote! {
         impl #impl_generics structecs::Extractable for #struct_name #ty_generics #where_clause {
             const METADATA_LIST: &'static [structecs::ExtractionMetadata] = &[
                 #metadata_list
             ];
 
-            #[cfg(debug_assertions)]
+            const REGISTERED: bool = #registered;
+
+            const ALLOW_DUPLICATES: bool = #allow_duplicates;
+
+            #on_destroy
+
+            #dyn_metadata
+
             const IDENTIFIER: &'static str = {
                 const MODULE_PATH: &str = module_path!();
-                const STRUCT_NAME: &str = stringify!(#struct_name);
+                const STRUCT_NAME: &str = #struct_name_str;
                 const TOTAL: usize = MODULE_PATH.len() + 2 + STRUCT_NAME.len();
                 const FULL_IDENTIFIER_BYTES: [u8; TOTAL] =
                     structecs::__private::concat_str::<TOTAL>(
@@ -138,8 +545,85 @@ fn expand(attr: Vec<Metadata<'_>>, input: &DeriveInput) -> syn::Result<TokenStre
             };
         }
 
-        structecs::__private::submit! {
-            structecs::ExtractableType::new::<#struct_name>()
-        }
+        #registration
+
+        #serde_registration
+
+        #contains
     })
 }
+
+/// Implement `Contains` for the type itself and for the types reachable through its
+/// fields at a fixed offset. Each field gets its own path marker, indexed by its
+/// position in the metadata list, so fields of the same type do not overlap.
+fn expand_contains(
+    attr: &[Metadata<'_>],
+    generics: &syn::Generics,
+    struct_name: &Ident,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Generics with the extra parameters of the impls forwarding to a nested type.
+    let mut forwarding = generics.clone();
+    forwarding
+        .params
+        .push(syn::parse_quote!(__StructecsTarget: 'static));
+    forwarding.params.push(syn::parse_quote!(__StructecsPath));
+
+    attr.iter()
+        .enumerate()
+        .map(|(index, attr)| match attr {
+            Metadata::Offset0 => quote::quote! {
+                unsafe impl #impl_generics structecs::Contains<Self, structecs::contains::Here>
+                    for #struct_name #ty_generics #where_clause
+                {
+                    const OFFSET: usize = 0;
+                }
+            },
+            Metadata::Leaf {
+                member,
+                target_type,
+            }
+            | Metadata::Field {
+                member,
+                target_type,
+            } => quote::quote! {
+                unsafe impl #impl_generics structecs::Contains<
+                    #target_type,
+                    structecs::contains::Field<#index, structecs::contains::Here>,
+                > for #struct_name #ty_generics #where_clause
+                {
+                    const OFFSET: usize = core::mem::offset_of!(Self, #member);
+                }
+            },
+            Metadata::Nested {
+                member,
+                target_type,
+            } => {
+                let mut generics = forwarding.clone();
+                generics
+                    .make_where_clause()
+                    .predicates
+                    .push(syn::parse_quote!(
+                        #target_type: structecs::Contains<__StructecsTarget, __StructecsPath>
+                    ));
+                let (impl_generics, _, where_clause) = generics.split_for_impl();
+                quote::quote! {
+                    unsafe impl #impl_generics structecs::Contains<
+                        __StructecsTarget,
+                        structecs::contains::Field<#index, __StructecsPath>,
+                    > for #struct_name #ty_generics #where_clause
+                    {
+                        const OFFSET: usize = core::mem::offset_of!(Self, #member)
+                            + <#target_type as structecs::Contains<
+                                __StructecsTarget,
+                                __StructecsPath,
+                            >>::OFFSET;
+                    }
+                }
+            }
+            // Only present while the enum holds the variant.
+            Metadata::Variant { .. } => TokenStream::new(),
+        })
+        .collect()
+}
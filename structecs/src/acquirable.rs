@@ -1,16 +1,21 @@
 use std::{
+    any::TypeId,
     fmt::Debug,
     ops::Deref,
     ptr::NonNull,
     sync::{Arc, Weak},
 };
 
-use crate::{Extractable, entity::EntityData};
+#[cfg(feature = "entity-id")]
+use crate::EntityId;
+use crate::{AcquirableDyn, Contains, Extractable, entity::EntityData};
 
 /// A smart pointer to a component that keeps the entity data alive.
 ///
 /// `Acquirable<T>` provides transparent access to component `T` through `Deref`,
 /// while maintaining ownership of the underlying entity data via reference counting.
+/// With the `locking` feature enabled, entities created with `new_locked` are accessed
+/// through `read()`/`write()` guards that lock the whole entity instead.
 ///
 /// # Thread Safety
 ///
@@ -37,8 +42,8 @@ use crate::{Extractable, entity::EntityData};
 /// // Access via Deref
 /// assert_eq!(player.name, "Alice");
 /// ```
-pub struct Acquirable<T: Extractable> {
-    target: NonNull<T>,
+pub struct Acquirable<T: 'static> {
+    pub(crate) target: NonNull<T>,
     pub(crate) inner: Arc<EntityData>,
 }
 
@@ -76,9 +81,12 @@ pub struct Acquirable<T: Extractable> {
 /// // Entity has been dropped
 /// assert!(weak.upgrade().is_none());
 /// ```
-pub struct WeakAcquirable<T: Extractable> {
+pub struct WeakAcquirable<T: 'static> {
+    target: NonNull<T>,
     inner: Weak<EntityData>,
-    _marker: std::marker::PhantomData<T>,
+    /// Kept here so it can be read without upgrading; `None` for `Default`.
+    #[cfg(feature = "entity-id")]
+    id: Option<EntityId>,
 }
 
 impl<T: Extractable> Acquirable<T> {
@@ -90,12 +98,12 @@ impl<T: Extractable> Acquirable<T> {
     /// Create an `Acquirable<T>` from a value of type `U` that contains `T`.
     ///
     /// This is a compile-time checked version that validates the type relationship
-    /// between `U` and `T` at compile time (in debug builds).
+    /// between `U` and `T` at compile time.
     ///
     /// # Compile-time Guarantees
     ///
-    /// In debug builds, this function includes a compile-time check that ensures
-    /// `U` contains `T` as an extractable component. The check is based on type
+    /// This function includes a compile-time check, in every build profile, that
+    /// ensures `U` contains `T` as an extractable component. The check is based on type
     /// metadata generated by the `#[derive(Extractable)]` macro.
     ///
     /// # Examples
@@ -123,43 +131,48 @@ impl<T: Extractable> Acquirable<T> {
     /// assert_eq!(entity.id, 42);
     /// ```
     ///
+    /// A type that does not contain `T` fails to compile:
+    ///
+    /// ```compile_fail
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity { id: u32 }
+    ///
+    /// #[derive(Extractable)]
+    /// struct Item { id: u32 }
+    ///
+    /// let entity: Acquirable<Entity> = Acquirable::new_checked(Item { id: 42 });
+    /// ```
+    ///
     /// # Panics
     ///
-    /// In debug builds, panics at compile-time if `U` does not contain `T`
-    /// as an extractable component.
+    /// Panics at compile-time if `U` does not contain `T` as an extractable component.
+    /// The check cannot tell instantiations of the same generic type apart, so a
+    /// mismatch between them panics at runtime instead.
     pub fn new_checked<U: Extractable>(target: U) -> Acquirable<T> {
-        #[cfg(debug_assertions)]
         const {
             if !crate::ExtractionMetadata::is_has::<U, T>() {
                 panic!("Type U must contain T as extractable component")
             }
         }
         let data = Arc::new(EntityData::new(target, crate::get_extractor::<U>()));
-        // SAFETY: unwrap_unchecked is safe here because:
-        // 1. The compile-time check above (in debug builds) ensures U contains T in its metadata
-        // 2. extract_ptr returns None only when the type is not found in the metadata
-        // 3. Since U contains T by construction (verified at compile-time), extract_ptr<T>()
-        //    will always return Some(ptr)
-        // 4. In release builds, incorrect usage is caught by the debug build tests
-        let extracted = unsafe { data.extract_ptr::<T>().unwrap_unchecked() };
+        // SAFETY: extract_ptr validates the type through the Extractor.
+        let extracted = unsafe { data.extract_ptr::<T>() }
+            .unwrap_or_else(|| crate::extractable::mismatch::<U, T>());
         Acquirable::new_raw(extracted, data)
     }
 
-    #[inline(always)]
-    pub(crate) fn new_raw(target: NonNull<T>, inner: Arc<EntityData>) -> Self {
-        Self { target, inner }
-    }
-
     /// Extract a component with compile-time type relationship checking.
     ///
     /// This is a compile-time checked version of [`extract`](Self::extract) that
-    /// validates the type relationship at compile time (in debug builds) and panics
-    /// instead of returning `None`.
+    /// validates the type relationship at compile time and panics instead of returning
+    /// `None`.
     ///
     /// # Compile-time Guarantees
     ///
-    /// In debug builds, this function includes a compile-time check that ensures
-    /// `T` contains `U` as an extractable component. The check is based on type
+    /// This function includes a compile-time check, in every build profile, that
+    /// ensures `T` contains `U` as an extractable component. The check is based on type
     /// metadata generated by the `#[derive(Extractable)]` macro.
     ///
     /// # Examples
@@ -192,28 +205,31 @@ impl<T: Extractable> Acquirable<T> {
     ///
     /// # Panics
     ///
-    /// In debug builds, panics at compile-time if `T` does not contain `U`
-    /// as an extractable component.
+    /// Panics at compile-time if `T` does not contain `U` as an extractable component.
+    /// The check cannot tell instantiations of the same generic type apart, so a
+    /// mismatch between them panics at runtime instead.
     ///
     /// # See Also
     ///
     /// - [`extract`](Self::extract) - Returns `Option<Acquirable<U>>` for runtime checking
     pub fn extract_checked<U: Extractable>(&self) -> Acquirable<U> {
-        #[cfg(debug_assertions)]
         const {
             if !crate::ExtractionMetadata::is_has::<T, U>() {
                 panic!("Type T must contain U as extractable component")
             }
         }
-        // SAFETY: unwrap_unchecked is safe here because:
-        // 1. The compile-time check above (in debug builds) ensures T contains U in its metadata
-        // 2. The EntityData was created with an Extractor that knows about all extractable types
-        // 3. extract_ptr returns None only when the type is not in the metadata
-        // 4. Since T contains U (verified at compile-time), extract_ptr<U>() always returns Some(ptr)
-        // 5. The pointer is valid as long as `self.inner` is alive, which is guaranteed by Arc
-        let extracted = unsafe { self.inner.extract_ptr::<U>().unwrap_unchecked() };
+        // SAFETY: extract_ptr validates the type through the Extractor.
+        let extracted = unsafe { self.inner.extract_ptr::<U>() }
+            .unwrap_or_else(|| crate::extractable::mismatch::<T, U>());
         Acquirable::new_raw(extracted, self.inner.clone())
     }
+}
+
+impl<T: 'static> Acquirable<T> {
+    #[inline(always)]
+    pub(crate) fn new_raw(target: NonNull<T>, inner: Arc<EntityData>) -> Self {
+        Self { target, inner }
+    }
 
     /// Extract a different component type from the same entity.
     ///
@@ -243,13 +259,267 @@ impl<T: Extractable> Acquirable<T> {
     /// assert_eq!(health.value, 100);
     /// ```
     #[inline(always)]
-    pub fn extract<U: Extractable>(&self) -> Option<Acquirable<U>> {
+    pub fn extract<U: 'static>(&self) -> Option<Acquirable<U>> {
         // SAFETY: extract_ptr performs type checking via the Extractor
         // and only returns a pointer if type U exists in the entity.
         let extracted = unsafe { self.inner.extract_ptr::<U>()? };
         Some(Acquirable::new_raw(extracted, self.inner.clone()))
     }
 
+    /// Get a component the type `T` always contains, without a runtime lookup.
+    ///
+    /// The bound is implemented by the derive (see [`contains`](crate::contains)), so
+    /// this cannot fail. The path `P` is inferred, and written as `_` when naming the
+    /// component: `entity.get::<Health, _>()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity {
+    ///     id: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(entity)]
+    /// struct Player {
+    ///     name: String,
+    ///     entity: Entity,
+    /// }
+    ///
+    /// let player = Acquirable::new(Player {
+    ///     name: "Alice".to_string(),
+    ///     entity: Entity { id: 1 },
+    /// });
+    ///
+    /// let entity: Acquirable<Entity> = player.get();
+    /// assert_eq!(entity.id, 1);
+    /// assert_eq!(player.get::<Entity, _>().id, 1);
+    /// ```
+    ///
+    /// Components that are not contained are rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity {
+    ///     id: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// struct Item {
+    ///     count: u32,
+    /// }
+    ///
+    /// let entity = Acquirable::new(Entity { id: 1 });
+    /// let _item = entity.get::<Item, _>();
+    /// ```
+    #[inline(always)]
+    pub fn get<U: 'static, P>(&self) -> Acquirable<U>
+    where
+        T: Contains<U, P>,
+    {
+        // SAFETY: `Contains` guarantees a `U` at `OFFSET` within every `T`, and the
+        // target points to a `T`.
+        let target = unsafe { self.target.byte_add(<T as Contains<U, P>>::OFFSET) }.cast::<U>();
+        Acquirable::new_raw(target, self.inner.clone())
+    }
+
+    /// Extract every occurrence of a component type from the same entity.
+    ///
+    /// Types containing the same component more than once must opt in with
+    /// `#[extractable(allow_duplicates = true)]`. For other types this returns at most
+    /// one element, the same one [`extract`](Self::extract) would return.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Item {
+    ///     id: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(main_hand, off_hand, allow_duplicates = true)]
+    /// struct Player {
+    ///     main_hand: Item,
+    ///     off_hand: Item,
+    /// }
+    ///
+    /// let player = Acquirable::new(Player {
+    ///     main_hand: Item { id: 1 },
+    ///     off_hand: Item { id: 2 },
+    /// });
+    ///
+    /// let items = player.extract_all::<Item>();
+    /// assert_eq!(items.iter().map(|item| item.id).collect::<Vec<_>>(), [1, 2]);
+    /// ```
+    #[inline]
+    pub fn extract_all<U: 'static>(&self) -> Vec<Acquirable<U>> {
+        self.inner.extract_all::<U>()
+    }
+
+    /// Extract the entity as a trait object, such as `dyn Tickable`.
+    ///
+    /// The trait object is the entity's concrete type, whichever component this
+    /// `Acquirable` points to. Returns `None` unless the concrete type lists the trait
+    /// with `#[extractable(dyn Trait)]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use structecs::*;
+    ///
+    /// trait Tickable {
+    ///     fn tick(&self) -> u32;
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity {
+    ///     id: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(entity, dyn Tickable)]
+    /// struct Zombie {
+    ///     entity: Entity,
+    ///     age: u32,
+    /// }
+    ///
+    /// impl Tickable for Zombie {
+    ///     fn tick(&self) -> u32 {
+    ///         self.age + 1
+    ///     }
+    /// }
+    ///
+    /// let zombie = Acquirable::new(Zombie {
+    ///     entity: Entity { id: 1 },
+    ///     age: 41,
+    /// });
+    /// let entity = zombie.extract::<Entity>().unwrap();
+    ///
+    /// let tickable = entity.extract_dyn::<dyn Tickable>().unwrap();
+    /// assert_eq!(tickable.tick(), 42);
+    /// ```
+    #[inline]
+    pub fn extract_dyn<D: ?Sized + 'static>(&self) -> Option<AcquirableDyn<D>> {
+        AcquirableDyn::new(&self.inner)
+    }
+
+    /// Get the nearest component of type `P` containing this one.
+    ///
+    /// Unlike [`extract`](Self::extract), which looks `P` up from the concrete type,
+    /// this walks up from the component through the structs nesting it, so it finds
+    /// the `P` holding this component when the entity contains several.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Health {
+    ///     value: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(health)]
+    /// struct LivingEntity {
+    ///     health: Health,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(living)]
+    /// struct Player {
+    ///     name: String,
+    ///     living: LivingEntity,
+    /// }
+    ///
+    /// let player = Acquirable::new(Player {
+    ///     name: "Alice".to_string(),
+    ///     living: LivingEntity {
+    ///         health: Health { value: 20 },
+    ///     },
+    /// });
+    /// let health = player.extract::<Health>().unwrap();
+    ///
+    /// let living = health.parent::<LivingEntity>().unwrap();
+    /// assert_eq!(living.health.value, 20);
+    /// assert_eq!(health.parent::<Player>().unwrap().name, "Alice");
+    /// assert!(health.parent::<Health>().is_none());
+    /// ```
+    pub fn parent<P: 'static>(&self) -> Option<Acquirable<P>> {
+        let mut path = self.inner.path_to(self.target)?;
+        path.pop();
+        let type_id = TypeId::of::<P>();
+        let parent = path
+            .into_iter()
+            .rev()
+            .find(|segment| segment.type_id == type_id)?;
+        Some(Acquirable::new_raw(parent.ptr.cast(), self.inner.clone()))
+    }
+
+    /// Get the names of the fields leading from the concrete type to this component.
+    ///
+    /// The path is empty for the concrete type itself. Fields of enum variants are
+    /// named after the variant, such as `Stone.0`.
+    ///
+    /// # Panics
+    ///
+    /// With the `locking` feature, panics if the component was stored in an enum
+    /// variant that has since been replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Health {
+    ///     value: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(health)]
+    /// struct LivingEntity {
+    ///     health: Health,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(living)]
+    /// struct Player {
+    ///     living: LivingEntity,
+    /// }
+    ///
+    /// let player = Acquirable::new(Player {
+    ///     living: LivingEntity {
+    ///         health: Health { value: 20 },
+    ///     },
+    /// });
+    ///
+    /// assert_eq!(player.extract::<Health>().unwrap().path(), ["living", "health"]);
+    /// assert!(player.path().is_empty());
+    /// ```
+    pub fn path(&self) -> Vec<&'static str> {
+        let Some(path) = self.inner.path_to(self.target) else {
+            panic!(
+                "Component `{}` is no longer present in entity `{}`: the enum variant \
+                 holding it was replaced",
+                std::any::type_name::<T>(),
+                self.inner.extractor.type_name
+            );
+        };
+        path.into_iter()
+            .skip(1)
+            .map(|segment| segment.field)
+            .collect()
+    }
+
     /// Create a weak reference to this entity's component.
     ///
     /// The weak reference does not keep the entity alive and can be upgraded
@@ -273,11 +543,68 @@ impl<T: Extractable> Acquirable<T> {
     #[inline(always)]
     pub fn downgrade(&self) -> WeakAcquirable<T> {
         WeakAcquirable {
+            target: self.target,
             inner: Arc::downgrade(&self.inner),
-            _marker: std::marker::PhantomData,
+            #[cfg(feature = "entity-id")]
+            id: Some(self.inner.id),
         }
     }
 
+    /// Register a hook called with this component when the entity is destroyed.
+    ///
+    /// Hooks run when the last strong reference to the entity is dropped, on the
+    /// thread dropping it, in registration order and before the hooks registered for
+    /// the concrete type (see [`registry::on_destroy`](crate::registry::on_destroy)).
+    /// Weak references can no longer be upgraded at that point.
+    ///
+    /// The hook is skipped if this component is stored in an enum variant that is no
+    /// longer present, e.g. because an earlier hook replaced it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, atomic::{AtomicU32, Ordering}};
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity {
+    ///     id: u32,
+    /// }
+    ///
+    /// let despawned = Arc::new(AtomicU32::new(0));
+    /// let entity = Acquirable::new(Entity { id: 42 });
+    ///
+    /// let hook = despawned.clone();
+    /// entity.on_destroy(move |entity| hook.store(entity.id, Ordering::SeqCst));
+    ///
+    /// drop(entity);
+    /// assert_eq!(despawned.load(Ordering::SeqCst), 42);
+    /// ```
+    pub fn on_destroy(&self, hook: impl FnOnce(&mut T) + Send + 'static) {
+        // SAFETY: The target points into the entity data.
+        let offset = unsafe {
+            self.target
+                .cast::<u8>()
+                .offset_from_unsigned(self.inner.data)
+        };
+        let extractor = self.inner.extractor;
+        let hook = move |data: NonNull<u8>| {
+            // SAFETY: Same offset as the target, within the same entity data.
+            let target = unsafe { data.byte_add(offset) }.cast::<T>();
+            // SAFETY: The entity is being destroyed, so nothing else accesses it.
+            if !unsafe { extractor.is_present(data, target) } {
+                return;
+            }
+            // SAFETY: The entity is being destroyed, so this is the only reference.
+            hook(unsafe { &mut *target.as_ptr() });
+        };
+        self.inner
+            .destroy_hooks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(Box::new(hook));
+    }
+
     /// Check if two `Acquirable` pointers point to the same entity data.
     ///
     /// # Examples
@@ -298,10 +625,129 @@ impl<T: Extractable> Acquirable<T> {
     /// assert!(!entity1.ptr_eq(&entity3));
     /// ```
     #[inline(always)]
-    pub fn ptr_eq<U: Extractable>(&self, other: &Acquirable<U>) -> bool {
+    pub fn ptr_eq<U: 'static>(&self, other: &Acquirable<U>) -> bool {
         Arc::ptr_eq(&self.inner, &other.inner)
     }
 
+    /// Get the ID of the entity, shared by every component extracted from it.
+    ///
+    /// Named so that it does not shadow `id` methods of components reached through
+    /// `Deref`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity {
+    ///     id: u32,
+    /// }
+    ///
+    /// let entity1 = Acquirable::new(Entity { id: 42 });
+    /// let entity2 = Acquirable::new(Entity { id: 42 });
+    ///
+    /// assert_eq!(entity1.entity_id(), entity1.clone().entity_id());
+    /// assert_ne!(entity1.entity_id(), entity2.entity_id());
+    /// ```
+    #[cfg(feature = "entity-id")]
+    #[inline(always)]
+    pub fn entity_id(&self) -> EntityId {
+        self.inner.id
+    }
+
+    /// Get the `TypeId` of the concrete type this entity was created as.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::any::TypeId;
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity {
+    ///     id: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(entity)]
+    /// struct Player {
+    ///     name: String,
+    ///     entity: Entity,
+    /// }
+    ///
+    /// let player = Acquirable::new(Player {
+    ///     name: "Alice".to_string(),
+    ///     entity: Entity { id: 1 },
+    /// });
+    /// let entity = player.extract::<Entity>().unwrap();
+    ///
+    /// assert_eq!(entity.concrete_type_id(), TypeId::of::<Player>());
+    /// ```
+    #[inline(always)]
+    pub fn concrete_type_id(&self) -> TypeId {
+        self.inner.extractor.type_id
+    }
+
+    /// Get the name of the concrete type this entity was created as.
+    ///
+    /// The name is the one returned by [`std::any::type_name`], and is intended for
+    /// diagnostics and logging.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity {
+    ///     id: u32,
+    /// }
+    ///
+    /// let entity = Acquirable::new(Entity { id: 42 });
+    /// assert!(entity.concrete_type_name().ends_with("Entity"));
+    /// ```
+    #[inline(always)]
+    pub fn concrete_type_name(&self) -> &'static str {
+        self.inner.extractor.type_name
+    }
+
+    /// Check whether this entity was created as the concrete type `U`.
+    ///
+    /// Unlike [`extract`](Self::extract), this does not succeed for types that are
+    /// merely contained in the entity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity {
+    ///     id: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(entity)]
+    /// struct Player {
+    ///     name: String,
+    ///     entity: Entity,
+    /// }
+    ///
+    /// let player = Acquirable::new(Player {
+    ///     name: "Alice".to_string(),
+    ///     entity: Entity { id: 1 },
+    /// });
+    /// let entity = player.extract::<Entity>().unwrap();
+    ///
+    /// assert!(entity.is::<Player>());
+    /// assert!(!entity.is::<Entity>());
+    /// ```
+    #[inline(always)]
+    pub fn is<U: 'static>(&self) -> bool {
+        self.concrete_type_id() == TypeId::of::<U>()
+    }
+
     /// Get the number of strong references to the entity data.
     ///
     /// This is only available in debug builds for debugging purposes.
@@ -361,10 +807,11 @@ impl<T: Extractable> Acquirable<T> {
     }
 }
 
-impl<T: Extractable> WeakAcquirable<T> {
+impl<T: 'static> WeakAcquirable<T> {
     /// Upgrade the weak reference to an `Acquirable` if the entity is still alive.
     ///
-    /// Returns `None` if the entity has been dropped.
+    /// Returns `None` if the entity has been dropped, or while it is still being
+    /// deserialized by `structecs::graph` (with the `serde` feature).
     ///
     /// # Examples
     ///
@@ -389,22 +836,64 @@ impl<T: Extractable> WeakAcquirable<T> {
     /// ```
     #[inline(always)]
     pub fn upgrade(&self) -> Option<Acquirable<T>> {
+        // The target was taken from an Acquirable<T> pointing into the same allocation,
+        // which is still alive if the upgrade succeeds. Keeping the pointer rather than
+        // looking T up again preserves which occurrence of T this reference was made from.
         let inner = self.inner.upgrade()?;
-        // SAFETY: unwrap_unchecked is safe here because:
-        // 1. WeakAcquirable<T> was created from an Acquirable<T> via downgrade()
-        // 2. The original Acquirable<T> was created with EntityData that contains type T
-        // 3. EntityData is immutable after creation - its type structure never changes
-        // 4. Therefore, if upgrade() succeeds (Arc is still alive), extract_ptr<T>()
-        //    will always find T in the same location it was originally stored
-        // 5. The returned pointer is valid for the lifetime of the upgraded Arc
-        Some(Acquirable::new_raw(
-            unsafe { inner.extract_ptr::<T>().unwrap_unchecked() },
-            inner,
-        ))
+        #[cfg(feature = "serde")]
+        if !inner.is_ready() {
+            return None;
+        }
+        Some(Acquirable::new_raw(self.target, inner))
+    }
+
+    /// Get the ID of the entity without upgrading, even after it has been dropped.
+    ///
+    /// Returns `None` only for a reference created with `Default`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Entity {
+    ///     id: u32,
+    /// }
+    ///
+    /// let entity = Acquirable::new(Entity { id: 42 });
+    /// let id = entity.entity_id();
+    /// let weak = entity.downgrade();
+    ///
+    /// drop(entity);
+    /// assert_eq!(weak.entity_id(), Some(id));
+    /// assert_eq!(WeakAcquirable::<Entity>::default().entity_id(), None);
+    /// ```
+    #[cfg(feature = "entity-id")]
+    #[inline(always)]
+    pub fn entity_id(&self) -> Option<EntityId> {
+        self.id
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline(always)]
+    pub(crate) fn new_raw(target: NonNull<T>, inner: &Arc<EntityData>) -> Self {
+        Self {
+            target,
+            inner: Arc::downgrade(inner),
+            #[cfg(feature = "entity-id")]
+            id: Some(inner.id),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline(always)]
+    pub(crate) fn inner(&self) -> &Weak<EntityData> {
+        &self.inner
     }
 }
 
-impl<T: Extractable> Clone for Acquirable<T> {
+impl<T: 'static> Clone for Acquirable<T> {
     #[inline(always)]
     fn clone(&self) -> Self {
         Self {
@@ -414,20 +903,29 @@ impl<T: Extractable> Clone for Acquirable<T> {
     }
 }
 
-impl<T: Extractable> Deref for Acquirable<T> {
+/// # Panics
+///
+/// With the `locking` feature, panics if the entity was created with `new_locked`.
+impl<T: 'static> Deref for Acquirable<T> {
     type Target = T;
 
     #[inline(always)]
     fn deref(&self) -> &Self::Target {
+        #[cfg(feature = "locking")]
+        self.inner.assert_unlocked();
         unsafe { self.target.as_ref() }
     }
 }
 
-impl<T: Extractable + Debug> Debug for Acquirable<T> {
+impl<T: 'static + Debug> Debug for Acquirable<T> {
     #[inline(always)]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(not(feature = "locking"))]
+        let target = &**self;
+        #[cfg(feature = "locking")]
+        let target = &*self.read();
         f.debug_struct("Acquirable")
-            .field("target", &**self)
+            .field("target", target)
             .finish()
     }
 }
@@ -437,21 +935,38 @@ impl<T: Extractable + Debug> Debug for Acquirable<T> {
 // Thread-safety guarantees:
 // - The `target` field is a NonNull<T> pointer that points into EntityData's heap allocation.
 //   The data is immutable after creation (no interior mutability), so shared references
-//   are safe across threads when T: Sync.
+//   are safe across threads when T: Sync. With the `locking` feature, mutable access is
+//   only handed out under the write lock of entities created with `new_locked`, which
+//   cannot be dereferenced without it.
 // - The `inner` field is an Arc<EntityData>, which provides thread-safe reference counting.
 //   Arc already implements Send when T: Send + Sync.
 // - Since T is accessed only through shared references (via Deref), we require T: Sync.
 // - We also require T: Send because the underlying data may be moved between threads
 //   when the last Arc is dropped on a different thread than where it was created.
-unsafe impl<T: Extractable + Send + Sync> Send for Acquirable<T> {}
-unsafe impl<T: Extractable + Send + Sync> Sync for Acquirable<T> {}
+unsafe impl<T: 'static + Send + Sync> Send for Acquirable<T> {}
+unsafe impl<T: 'static + Send + Sync> Sync for Acquirable<T> {}
 
-impl<T: Extractable> Clone for WeakAcquirable<T> {
+/// Creates a weak reference that never upgrades, like [`Weak::new`].
+impl<T: 'static> Default for WeakAcquirable<T> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self {
+            target: NonNull::dangling(),
+            inner: Weak::new(),
+            #[cfg(feature = "entity-id")]
+            id: None,
+        }
+    }
+}
+
+impl<T: 'static> Clone for WeakAcquirable<T> {
     #[inline(always)]
     fn clone(&self) -> Self {
         Self {
+            target: self.target,
             inner: self.inner.clone(),
-            _marker: std::marker::PhantomData,
+            #[cfg(feature = "entity-id")]
+            id: self.id,
         }
     }
 }
@@ -461,10 +976,10 @@ impl<T: Extractable> Clone for WeakAcquirable<T> {
 // Thread-safety guarantees:
 // - The `inner` field is a Weak<EntityData>, which provides thread-safe weak reference counting.
 //   Weak already implements Send when T: Send + Sync.
-// - WeakAcquirable does not directly hold any data; it only holds a weak reference.
-//   When upgraded to Acquirable<T>, the same Send + Sync bounds apply.
-// - The PhantomData<T> marker is zero-sized and does not affect thread safety.
+// - The `target` pointer is never dereferenced by WeakAcquirable itself; it is only
+//   handed to the Acquirable<T> created by a successful upgrade, where the same Send +
+//   Sync bounds apply.
 // - We require T: Send + Sync for the same reasons as Acquirable<T>: the underlying
 //   data must be safely transferable and shareable across threads.
-unsafe impl<T: Extractable + Send + Sync> Send for WeakAcquirable<T> {}
-unsafe impl<T: Extractable + Send + Sync> Sync for WeakAcquirable<T> {}
+unsafe impl<T: 'static + Send + Sync> Send for WeakAcquirable<T> {}
+unsafe impl<T: 'static + Send + Sync> Sync for WeakAcquirable<T> {}
@@ -0,0 +1,137 @@
+use std::{any::TypeId, fmt::Debug, ops::Deref, ptr::NonNull, sync::Arc};
+
+#[cfg(feature = "entity-id")]
+use crate::EntityId;
+use crate::{Acquirable, entity::EntityData};
+
+/// A smart pointer to an entity viewed as a trait object, such as `dyn Tickable`.
+///
+/// Created with [`Acquirable::extract_dyn`] for concrete types listing the trait with
+/// `#[extractable(dyn Trait)]`. Like `Acquirable<T>`, it keeps the entity alive and
+/// gives access through `Deref`, or through `read()`/`write()` guards for entities
+/// created with `Acquirable::new_locked`.
+///
+/// # Examples
+///
+/// ```
+/// use structecs::*;
+///
+/// trait Damageable {
+///     fn health(&self) -> u32;
+/// }
+///
+/// #[derive(Extractable)]
+/// #[extractable(dyn Damageable)]
+/// struct Player {
+///     health: u32,
+/// }
+///
+/// impl Damageable for Player {
+///     fn health(&self) -> u32 {
+///         self.health
+///     }
+/// }
+///
+/// let player = Acquirable::new(Player { health: 20 });
+/// let damageable: AcquirableDyn<dyn Damageable> = player.extract_dyn().unwrap();
+///
+/// assert_eq!(damageable.health(), 20);
+/// assert!(damageable.extract::<Player>().unwrap().ptr_eq(&player));
+/// ```
+pub struct AcquirableDyn<D: ?Sized + 'static> {
+    pub(crate) target: NonNull<D>,
+    pub(crate) inner: Arc<EntityData>,
+}
+
+impl<D: ?Sized + 'static> AcquirableDyn<D> {
+    #[inline]
+    pub(crate) fn new(inner: &Arc<EntityData>) -> Option<Self> {
+        let cast = inner.extractor.dyn_cast::<D>()?;
+        Some(Self {
+            target: cast(inner.data),
+            inner: inner.clone(),
+        })
+    }
+
+    /// Extract a component from the entity.
+    #[inline]
+    pub fn extract<U: 'static>(&self) -> Option<Acquirable<U>> {
+        self.inner.extract::<U>()
+    }
+
+    /// Extract the entity as another trait object.
+    #[inline]
+    pub fn extract_dyn<E: ?Sized + 'static>(&self) -> Option<AcquirableDyn<E>> {
+        AcquirableDyn::new(&self.inner)
+    }
+
+    /// Check if this points to the same entity as `other`.
+    #[inline(always)]
+    pub fn ptr_eq<U: 'static>(&self, other: &Acquirable<U>) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Get the ID of the entity.
+    #[cfg(feature = "entity-id")]
+    #[inline(always)]
+    pub fn entity_id(&self) -> EntityId {
+        self.inner.id
+    }
+
+    /// Get the `TypeId` of the concrete type this entity was created as.
+    #[inline(always)]
+    pub fn concrete_type_id(&self) -> TypeId {
+        self.inner.extractor.type_id
+    }
+
+    /// Get the name of the concrete type this entity was created as.
+    #[inline(always)]
+    pub fn concrete_type_name(&self) -> &'static str {
+        self.inner.extractor.type_name
+    }
+}
+
+impl<D: ?Sized + 'static> Clone for AcquirableDyn<D> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// # Panics
+///
+/// With the `locking` feature, panics if the entity was created with
+/// `Acquirable::new_locked`.
+impl<D: ?Sized + 'static> Deref for AcquirableDyn<D> {
+    type Target = D;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        #[cfg(feature = "locking")]
+        self.inner.assert_unlocked();
+        // SAFETY: The target points to the entity's concrete type, which lives as long
+        // as `inner`.
+        unsafe { self.target.as_ref() }
+    }
+}
+
+impl<D: ?Sized + 'static + Debug> Debug for AcquirableDyn<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(not(feature = "locking"))]
+        let target = &**self;
+        #[cfg(feature = "locking")]
+        let target = &*self.read();
+        f.debug_struct("AcquirableDyn")
+            .field("target", &target)
+            .finish()
+    }
+}
+
+// SAFETY: Same reasoning as for Acquirable<T>: the entity data is reference counted by
+// an Arc, and the trait object is only accessed through shared references (or under the
+// entity lock with the `locking` feature), so D: Send + Sync is required.
+unsafe impl<D: ?Sized + 'static + Send + Sync> Send for AcquirableDyn<D> {}
+unsafe impl<D: ?Sized + 'static + Send + Sync> Sync for AcquirableDyn<D> {}
@@ -8,14 +8,20 @@
 //!
 //! Unlike traditional ECS archetypes, this implementation is **optional** and **minimal**:
 //! - Users can access the underlying `Arc<RwLock<HashMap>>` via `inner()` for custom operations
-//! - Additional API methods are added only when commonly needed
+//! - Additional API methods are added only when commonly needed, such as `iter_as` for
+//!   iterating the entities containing a specific type
 //! - The collection stores `Acquirable<Base>`, allowing extraction back to specific types
 //!
+//! Keys are also indexed by the concrete type of their entity, so `iter_as` only visits
+//! the entities of types that can contain the requested one.
+//!
 //! # Compile-time Safety
 //!
 //! When inserting a value of type `U`, the compiler ensures that `U` contains `Base` as an
-//! extractable component. This check happens at compile time (in debug builds), preventing
-//! runtime type errors.
+//! extractable component. This check happens at compile time in every build profile,
+//! preventing runtime type errors. It compares type identifiers, which do not include
+//! generic arguments, so inserting a different instantiation of a generic `Base` is
+//! only caught at runtime.
 //!
 //! # Example
 //!
@@ -56,13 +62,22 @@
 //!
 //! `Archetype` is `Clone` (cheap Arc clone) and `Send + Sync`. Multiple clones share the same
 //! underlying data, protected by a `RwLock` for concurrent access.
+//!
+//! # Serialization
+//!
+//! With the `serde` feature, an `Archetype` serializes as a map from keys to entities,
+//! each written as its concrete type with the tag it was registered under (see
+//! [`tagged`](crate::tagged)). Deserializing creates new entities of the tagged types.
 
-use std::{hash::Hash, sync::Arc};
+use std::{any::TypeId, hash::Hash, sync::Arc};
 
-use parking_lot::RwLock;
-use rustc_hash::FxHashMap;
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use rustc_hash::{FxHashMap, FxHashSet};
 
-use crate::{Acquirable, Extractable};
+use crate::{
+    Acquirable, Extractable, Extractor,
+    observer::{ArchetypeEvent, Observers, SubscriptionId},
+};
 
 /// A thread-safe collection that stores `Acquirable<Base>` values indexed by `Key`.
 ///
@@ -70,44 +85,323 @@ use crate::{Acquirable, Extractable};
 #[derive(Debug)]
 pub struct Archetype<Key: Copy + Eq + Hash, Base: Extractable> {
     map: Arc<RwLock<FxHashMap<Key, Acquirable<Base>>>>,
+    /// Always locked after `map`.
+    index: Arc<RwLock<TypeIndex<Key>>>,
+    observers: Arc<Observers<Key, Base>>,
 }
 
-impl<Key: Copy + Eq + Hash, Base: Extractable> Default for Archetype<Key, Base> {
-    fn default() -> Self {
+/// The entries of an archetype, with their keys grouped by concrete type.
+#[derive(Debug)]
+pub(crate) struct Entries<Key, Base: 'static> {
+    pub(crate) map: FxHashMap<Key, Acquirable<Base>>,
+    index: TypeIndex<Key>,
+}
+
+impl<Key: Copy, Base: 'static> Clone for Entries<Key, Base> {
+    fn clone(&self) -> Self {
         Self {
-            map: Arc::new(RwLock::new(FxHashMap::default())),
+            map: self.map.clone(),
+            index: self.index.clone(),
+        }
+    }
+}
+
+impl<Key: Copy + Eq + Hash, Base: 'static> Entries<Key, Base> {
+    pub(crate) fn new(map: FxHashMap<Key, Acquirable<Base>>) -> Self {
+        let index = TypeIndex::new(&map);
+        Self { map, index }
+    }
+
+    pub(crate) fn insert(&mut self, key: Key, value: Acquirable<Base>) -> Option<Acquirable<Base>> {
+        self.index.insert(&mut self.map, key, value)
+    }
+
+    pub(crate) fn remove(&mut self, key: &Key) -> Option<Acquirable<Base>> {
+        self.index.remove(&mut self.map, key)
+    }
+
+    /// Remove every entry, returning them so they can be dropped after unlocking.
+    pub(crate) fn take(&mut self) -> FxHashMap<Key, Acquirable<Base>> {
+        self.index.clear();
+        std::mem::take(&mut self.map)
+    }
+
+    /// Push the entries whose entity contains `U` onto `out`.
+    pub(crate) fn collect_as<U: 'static>(&self, out: &mut Vec<(Key, Acquirable<U>)>) {
+        self.index.collect_as(&self.map, out);
+    }
+
+    /// Remove the entries for which `predicate` returns `true`, pushing them onto `out`.
+    pub(crate) fn drain_filter(
+        &mut self,
+        predicate: &mut impl FnMut(&Key, &Acquirable<Base>) -> bool,
+        out: &mut Vec<(Key, Acquirable<Base>)>,
+    ) {
+        self.index.drain_filter(&mut self.map, predicate, out);
+    }
+
+    /// Mark the index as no longer matching `map`, after modifying `map` directly.
+    pub(crate) fn invalidate(&mut self) {
+        self.index.invalidate();
+    }
+
+    /// Rebuild the index from `map`, if it is stale.
+    pub(crate) fn reindex(&mut self) {
+        self.index.reindex(&self.map);
+    }
+}
+
+/// The keys of a map of entities, grouped by the concrete type of the entity.
+#[derive(Debug, Clone)]
+struct TypeIndex<Key> {
+    by_type: FxHashMap<TypeId, TypeBucket<Key>>,
+    /// Set when the map was handed out for writing, and `by_type` may no longer
+    /// match it.
+    stale: bool,
+}
+
+/// The keys of the entities of one concrete type.
+#[derive(Debug, Clone)]
+struct TypeBucket<Key> {
+    extractor: &'static Extractor,
+    keys: FxHashSet<Key>,
+}
+
+impl<Key: Copy + Eq + Hash> TypeIndex<Key> {
+    fn new<Base>(map: &FxHashMap<Key, Acquirable<Base>>) -> Self {
+        let mut index = Self {
+            by_type: FxHashMap::default(),
+            stale: true,
+        };
+        index.reindex(map);
+        index
+    }
+
+    fn insert<Base>(
+        &mut self,
+        map: &mut FxHashMap<Key, Acquirable<Base>>,
+        key: Key,
+        value: Acquirable<Base>,
+    ) -> Option<Acquirable<Base>> {
+        if !self.stale {
+            index_key(&mut self.by_type, key, &value);
+        }
+        let previous = map.insert(key, value);
+        if let Some(previous) = &previous {
+            self.unindex(map, &key, previous);
+        }
+        previous
+    }
+
+    fn remove<Base>(
+        &mut self,
+        map: &mut FxHashMap<Key, Acquirable<Base>>,
+        key: &Key,
+    ) -> Option<Acquirable<Base>> {
+        let previous = map.remove(key);
+        if let Some(previous) = &previous {
+            self.unindex(map, key, previous);
+        }
+        previous
+    }
+
+    fn drain_filter<Base>(
+        &mut self,
+        map: &mut FxHashMap<Key, Acquirable<Base>>,
+        predicate: &mut impl FnMut(&Key, &Acquirable<Base>) -> bool,
+        out: &mut Vec<(Key, Acquirable<Base>)>,
+    ) {
+        let start = out.len();
+        out.extend(map.extract_if(|key, value| predicate(key, value)));
+        for (key, value) in &out[start..] {
+            self.unindex(map, key, value);
         }
     }
+
+    fn unindex<Base>(
+        &mut self,
+        map: &FxHashMap<Key, Acquirable<Base>>,
+        key: &Key,
+        value: &Acquirable<Base>,
+    ) {
+        if self.stale {
+            return;
+        }
+        let type_id = value.inner.extractor.type_id;
+        if let Some(bucket) = self.by_type.get_mut(&type_id) {
+            // An entry replaced by one of the same type keeps its key indexed.
+            if map
+                .get(key)
+                .is_none_or(|current| current.inner.extractor.type_id != type_id)
+            {
+                bucket.keys.remove(key);
+            }
+            if bucket.keys.is_empty() {
+                self.by_type.remove(&type_id);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.by_type.clear();
+    }
+
+    /// Push the entries of `map` whose entity contains `U` onto `out`, visiting only
+    /// the entities of concrete types that can contain it.
+    fn collect_as<Base, U: 'static>(
+        &self,
+        map: &FxHashMap<Key, Acquirable<Base>>,
+        out: &mut Vec<(Key, Acquirable<U>)>,
+    ) {
+        debug_assert!(!self.stale, "collect_as requires an up to date index");
+        let type_id = TypeId::of::<U>();
+        out.extend(
+            self.by_type
+                .values()
+                .filter(|bucket| bucket.extractor.may_contain(type_id))
+                .flat_map(|bucket| &bucket.keys)
+                .filter_map(|key| {
+                    let value = map.get(key)?.extract::<U>()?;
+                    Some((*key, value))
+                }),
+        );
+    }
+
+    fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    /// Rebuild `by_type` from `map`, if it is stale.
+    fn reindex<Base>(&mut self, map: &FxHashMap<Key, Acquirable<Base>>) {
+        if !self.stale {
+            return;
+        }
+        self.by_type.clear();
+        for (key, value) in map {
+            index_key(&mut self.by_type, *key, value);
+        }
+        self.stale = false;
+    }
+}
+
+fn index_key<Key: Copy + Eq + Hash, Base>(
+    by_type: &mut FxHashMap<TypeId, TypeBucket<Key>>,
+    key: Key,
+    value: &Acquirable<Base>,
+) {
+    let extractor = value.inner.extractor;
+    by_type
+        .entry(extractor.type_id)
+        .or_insert_with(|| TypeBucket {
+            extractor,
+            keys: FxHashSet::default(),
+        })
+        .keys
+        .insert(key);
+}
+
+/// Create an entity from `value`, along with the `Base` stored for it in an archetype.
+pub(crate) fn new_entity<U: Extractable, Base: Extractable>(
+    value: U,
+) -> (Acquirable<U>, Acquirable<Base>) {
+    with_base(Acquirable::new(value))
+}
+
+/// Create a locked entity from `value`, along with the `Base` stored for it in an
+/// archetype.
+#[cfg(feature = "locking")]
+pub(crate) fn new_locked_entity<U: Extractable, Base: Extractable>(
+    value: U,
+) -> (Acquirable<U>, Acquirable<Base>) {
+    with_base(Acquirable::new_locked(value))
+}
+
+fn with_base<U: Extractable, Base: Extractable>(
+    acquirable: Acquirable<U>,
+) -> (Acquirable<U>, Acquirable<Base>) {
+    const {
+        if !crate::ExtractionMetadata::is_has::<U, Base>() {
+            panic!("Type U must contain Base as extractable component")
+        }
+    }
+
+    let base = acquirable
+        .inner
+        .extract::<Base>()
+        .unwrap_or_else(|| crate::extractable::mismatch::<U, Base>());
+    (acquirable, base)
+}
+
+impl<Key: Copy + Eq + Hash, Base: Extractable> Default for Archetype<Key, Base> {
+    fn default() -> Self {
+        Self::from_map(FxHashMap::default())
+    }
 }
 
 impl<Key: Copy + Eq + Hash, Base: Extractable> Clone for Archetype<Key, Base> {
     fn clone(&self) -> Self {
         Self {
             map: Arc::clone(&self.map),
+            index: Arc::clone(&self.index),
+            observers: Arc::clone(&self.observers),
         }
     }
 }
 
 impl<Key: Copy + Eq + Hash, Base: Extractable> Archetype<Key, Base> {
-    pub fn insert<U: Extractable>(&self, key: Key, value: U) -> Acquirable<U> {
-        #[cfg(debug_assertions)]
-        const {
-            if !crate::ExtractionMetadata::is_has::<U, Base>() {
-                panic!("Type U must contain Base as extractable component")
-            }
+    fn from_map(map: FxHashMap<Key, Acquirable<Base>>) -> Self {
+        Self {
+            index: Arc::new(RwLock::new(TypeIndex::new(&map))),
+            map: Arc::new(RwLock::new(map)),
+            observers: Arc::default(),
         }
+    }
 
-        let acquirable = Acquirable::new(value);
-        // SAFETY: The compile-time check above ensures that U contains Base as an extractable component.
-        // Therefore, extracting Base from U is guaranteed to succeed.
-        let insert = unsafe { acquirable.inner.extract::<Base>().unwrap_unchecked() };
-
-        let mut map = self.map.write();
-        map.insert(key, insert);
+    /// Create an entity from `value` and store it under `key`, replacing any previous
+    /// entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics at compile-time if `U` does not contain `Base` as an extractable
+    /// component. The check cannot tell instantiations of the same generic type apart,
+    /// so a mismatch between them panics at runtime instead.
+    pub fn insert<U: Extractable>(&self, key: Key, value: U) -> Acquirable<U> {
+        let (acquirable, insert) = new_entity::<U, Base>(value);
+        self.store(key, insert);
+        acquirable
+    }
 
+    /// Like [`insert`](Self::insert), but create the entity with
+    /// [`Acquirable::new_locked`], so its components can be modified through
+    /// [`Acquirable::write`].
+    #[cfg(feature = "locking")]
+    pub fn insert_locked<U: Extractable>(&self, key: Key, value: U) -> Acquirable<U> {
+        let (acquirable, insert) = new_locked_entity::<U, Base>(value);
+        self.store(key, insert);
         acquirable
     }
 
+    fn store(&self, key: Key, insert: Acquirable<Base>) {
+        let mut map = self.map.write();
+        if !self.observers.is_observed() {
+            let replaced = self.index.write().insert(&mut map, key, insert);
+            // Dropped after the lock is released, as despawn hooks may access this
+            // archetype.
+            drop(map);
+            drop(replaced);
+            return;
+        }
+        let event = match self.index.write().insert(&mut map, key, insert.clone()) {
+            Some(old) => ArchetypeEvent::Replaced {
+                key,
+                old,
+                new: insert,
+            },
+            None => ArchetypeEvent::Inserted { key, value: insert },
+        };
+        self.observers.notify(map, [event]);
+    }
+
     pub fn get(&self, key: &Key) -> Option<Acquirable<Base>> {
         let map = self.map.read();
         map.get(key).cloned()
@@ -115,7 +409,17 @@ impl<Key: Copy + Eq + Hash, Base: Extractable> Archetype<Key, Base> {
 
     pub fn remove(&self, key: &Key) -> Option<Acquirable<Base>> {
         let mut map = self.map.write();
-        map.remove(key)
+        let removed = self.index.write().remove(&mut map, key);
+        if let Some(value) = &removed
+            && self.observers.is_observed()
+        {
+            let event = ArchetypeEvent::Removed {
+                key: *key,
+                value: value.clone(),
+            };
+            self.observers.notify(map, [event]);
+        }
+        removed
     }
 
     pub fn contains_key(&self, key: &Key) -> bool {
@@ -135,24 +439,253 @@ impl<Key: Copy + Eq + Hash, Base: Extractable> Archetype<Key, Base> {
 
     pub fn clear(&self) {
         let mut map = self.map.write();
-        map.clear();
+        self.index.write().clear();
+        let cleared = std::mem::take(&mut *map);
+        if !self.observers.is_observed() {
+            // Dropped after the lock is released, as in `insert`.
+            drop(map);
+            drop(cleared);
+            return;
+        }
+        let events = cleared
+            .into_iter()
+            .map(|(key, value)| ArchetypeEvent::Removed { key, value });
+        self.observers.notify(map, events);
+    }
+
+    /// Collect the entries whose entity contains `U`, extracted as `U`.
+    ///
+    /// Only the entities of concrete types that can contain `U` are visited, so this is
+    /// proportional to the number of matching entries rather than the size of the
+    /// archetype.
+    ///
+    /// The entries are collected under the read lock, which is released before the
+    /// iterator is returned, so the archetype can be modified while iterating.
+    pub fn iter_as<U: 'static>(&self) -> std::vec::IntoIter<(Key, Acquirable<U>)> {
+        let mut out = Vec::new();
+        let map = self.map.read();
+        self.read_index(&map).collect_as(&map, &mut out);
+        out.into_iter()
+    }
+
+    /// Call `f` on every entry.
+    ///
+    /// `f` runs under the read lock, so it must not modify this archetype.
+    pub fn for_each(&self, mut f: impl FnMut(&Key, &Acquirable<Base>)) {
+        let map = self.map.read();
+        for (key, value) in map.iter() {
+            f(key, value);
+        }
+    }
+
+    /// Collect the entries for which `predicate` returns `true`.
+    ///
+    /// `predicate` runs under the read lock, so it must not modify this archetype.
+    pub fn filter(
+        &self,
+        mut predicate: impl FnMut(&Key, &Acquirable<Base>) -> bool,
+    ) -> Vec<(Key, Acquirable<Base>)> {
+        let map = self.map.read();
+        map.iter()
+            .filter(|(key, value)| predicate(key, value))
+            .map(|(key, value)| (*key, value.clone()))
+            .collect()
+    }
+
+    /// Remove the entries for which `predicate` returns `false`.
+    ///
+    /// `predicate` runs under the write lock, so it must not access this archetype.
+    /// Removed entities are dropped after the lock is released.
+    pub fn retain(&self, mut predicate: impl FnMut(&Key, &Acquirable<Base>) -> bool) {
+        drop(self.drain_filter(|key, value| !predicate(key, value)));
     }
 
-    pub fn read(&self) -> parking_lot::RwLockReadGuard<'_, FxHashMap<Key, Acquirable<Base>>> {
+    /// Remove and return the entries for which `predicate` returns `true`.
+    ///
+    /// `predicate` runs under the write lock, so it must not access this archetype.
+    pub fn drain_filter(
+        &self,
+        mut predicate: impl FnMut(&Key, &Acquirable<Base>) -> bool,
+    ) -> Vec<(Key, Acquirable<Base>)> {
+        let mut drained = Vec::new();
+        let mut map = self.map.write();
+        self.index
+            .write()
+            .drain_filter(&mut map, &mut predicate, &mut drained);
+        if self.observers.is_observed() {
+            let events = drained.iter().map(|(key, value)| ArchetypeEvent::Removed {
+                key: *key,
+                value: value.clone(),
+            });
+            self.observers.notify(map, events);
+        }
+        drained
+    }
+
+    /// Register `observer` to be called on every insertion, overwrite and removal.
+    ///
+    /// Observers are called after the archetype is unlocked, so they may access it,
+    /// including modifying it. Modifications made directly through
+    /// [`write`](Self::write) are not observed. See the [`observer`](crate::observer)
+    /// module for when events are delivered.
+    pub fn subscribe(
+        &self,
+        observer: impl Fn(&ArchetypeEvent<Key, Base>) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        self.observers.subscribe(observer)
+    }
+
+    /// Register `observer` to be called on changes to the entities containing `U`,
+    /// extracted as `U`.
+    ///
+    /// See [`ArchetypeEvent::extract`] for how overwrites are reported.
+    pub fn subscribe_as<U: 'static>(
+        &self,
+        observer: impl Fn(&ArchetypeEvent<Key, U>) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        self.observers.subscribe(move |event| {
+            if let Some(event) = event.extract::<U>() {
+                observer(&event);
+            }
+        })
+    }
+
+    /// Remove an observer, returning whether it was registered.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        self.observers.unsubscribe(id)
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, FxHashMap<Key, Acquirable<Base>>> {
         self.map.read()
     }
 
-    pub fn write(&self) -> parking_lot::RwLockWriteGuard<'_, FxHashMap<Key, Acquirable<Base>>> {
-        self.map.write()
+    /// Lock the entries for writing.
+    ///
+    /// Modifying the map directly bypasses the per-type index used by
+    /// [`iter_as`](Self::iter_as), which is rebuilt by the next query.
+    pub fn write(&self) -> RwLockWriteGuard<'_, FxHashMap<Key, Acquirable<Base>>> {
+        let map = self.map.write();
+        self.index.write().invalidate();
+        map
     }
 
+    /// The shared map of entries.
+    ///
+    /// Like [`write`](Self::write), this marks the per-type index used by
+    /// [`iter_as`](Self::iter_as) for a rebuild by the next query. Changes made through
+    /// a handle kept across queries are not seen by `iter_as`; call `inner` again, or
+    /// use `write`, before each change.
     pub fn inner(&self) -> &Arc<RwLock<FxHashMap<Key, Acquirable<Base>>>> {
+        self.index.write().invalidate();
         &self.map
     }
 
+    /// Take the shared map of entries. See [`inner`](Self::inner).
     pub fn into_inner(self) -> Arc<RwLock<FxHashMap<Key, Acquirable<Base>>>> {
+        self.index.write().invalidate();
         self.map
     }
+
+    /// Read-lock the per-type index of `map`, rebuilding it first if it is stale.
+    fn read_index(
+        &self,
+        map: &FxHashMap<Key, Acquirable<Base>>,
+    ) -> RwLockReadGuard<'_, TypeIndex<Key>> {
+        let index = self.index.read();
+        if !index.stale {
+            return index;
+        }
+        drop(index);
+        let mut index = self.index.write();
+        index.reindex(map);
+        RwLockWriteGuard::downgrade(index)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<Key, Base> serde::Serialize for Archetype<Key, Base>
+where
+    Key: Copy + Eq + Hash + serde::Serialize,
+    Base: Extractable,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let map = self.read();
+        serialize_entries(serializer, map.len(), map.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, Key, Base> serde::Deserialize<'de> for Archetype<Key, Base>
+where
+    Key: Copy + Eq + Hash + serde::Deserialize<'de>,
+    Base: Extractable,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = deserialize_entries(deserializer)?;
+        Ok(Archetype::from_map(map))
+    }
+}
+
+/// Serialize entries as a map from keys to tagged entities.
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_entries<'a, S, Key, Base>(
+    serializer: S,
+    len: usize,
+    entries: impl Iterator<Item = (&'a Key, &'a Acquirable<Base>)>,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    Key: serde::Serialize + 'a,
+    Base: 'static,
+{
+    use serde::ser::SerializeMap;
+
+    let mut state = serializer.serialize_map(Some(len))?;
+    for (key, value) in entries {
+        state.serialize_entry(key, &crate::tagged::Tagged(value))?;
+    }
+    state.end()
+}
+
+/// Deserialize what [`serialize_entries`] serializes.
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_entries<'de, D, Key, Base>(
+    deserializer: D,
+) -> Result<FxHashMap<Key, Acquirable<Base>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    Key: Copy + Eq + Hash + serde::Deserialize<'de>,
+    Base: Extractable,
+{
+    struct EntriesVisitor<Key, Base>(std::marker::PhantomData<(Key, Base)>);
+
+    impl<'de, Key, Base> serde::de::Visitor<'de> for EntriesVisitor<Key, Base>
+    where
+        Key: Copy + Eq + Hash + serde::Deserialize<'de>,
+        Base: Extractable,
+    {
+        type Value = FxHashMap<Key, Acquirable<Base>>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a map of tagged entities")
+        }
+
+        fn visit_map<A: serde::de::MapAccess<'de>>(
+            self,
+            mut access: A,
+        ) -> Result<Self::Value, A::Error> {
+            let mut map = FxHashMap::default();
+            map.reserve(access.size_hint().unwrap_or(0));
+            while let Some(key) = access.next_key()? {
+                let value =
+                    access.next_value_seed(crate::tagged::TaggedSeed(std::marker::PhantomData))?;
+                map.insert(key, value);
+            }
+            Ok(map)
+        }
+    }
+
+    deserializer.deserialize_map(EntriesVisitor(std::marker::PhantomData))
 }
 
 #[cfg(test)]
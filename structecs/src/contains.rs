@@ -0,0 +1,69 @@
+//! Type-level containment, for generic code that extracts components infallibly.
+//!
+//! The derive implements [`Contains<T, P>`] for the type itself and for every type
+//! it contains at a fixed offset, through the fields listed for extraction. `P` is a
+//! path marker built from [`Here`] and [`Field`], which keeps the implementations for
+//! different fields apart. It is inferred, so bounds name it as an extra parameter:
+//!
+//! ```rust
+//! use structecs::*;
+//!
+//! #[derive(Extractable)]
+//! struct Health {
+//!     current: u32,
+//! }
+//!
+//! #[derive(Extractable)]
+//! #[extractable(health)]
+//! struct LivingEntity {
+//!     health: Health,
+//! }
+//!
+//! #[derive(Extractable)]
+//! #[extractable(living)]
+//! struct Player {
+//!     name: String,
+//!     living: LivingEntity,
+//! }
+//!
+//! fn health<E: Contains<Health, P>, P>(entity: &Acquirable<E>) -> u32 {
+//!     entity.get::<Health, _>().current
+//! }
+//!
+//! let player = Acquirable::new(Player {
+//!     name: "Alice".to_string(),
+//!     living: LivingEntity {
+//!         health: Health { current: 20 },
+//!     },
+//! });
+//! assert_eq!(health(&player), 20);
+//! ```
+//!
+//! The path parameter cannot be left out, as in `Contains<Health>`: the derive only
+//! sees its own fields, so it forwards through each nested field to whatever the
+//! field's type contains, and these impls would overlap without the path telling
+//! them apart. A type reachable through several fields has one path for each, so the
+//! path has to be spelled out for it. Types stored in enum variants are not contained,
+//! since they are only present while the enum holds the variant.
+
+use std::marker::PhantomData;
+
+use crate::Extractable;
+
+/// Implemented by types containing a `T` at a fixed offset, reached through the path `P`.
+///
+/// # Safety
+///
+/// Every value of the implementing type must hold a valid `T` at byte offset
+/// [`OFFSET`](Self::OFFSET). The derive is the intended implementor.
+pub unsafe trait Contains<T: 'static, P>: Extractable {
+    /// The byte offset of the `T` within the implementing type.
+    const OFFSET: usize;
+}
+
+/// Path marker for the type itself.
+pub struct Here;
+
+/// Path marker for the field at `INDEX` in the extraction metadata, followed by `Next`
+/// within the field.
+pub struct Field<const INDEX: usize, Next>(PhantomData<Next>);
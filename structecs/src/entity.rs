@@ -1,23 +1,106 @@
-use std::{ptr::NonNull, sync::Arc};
+use std::{
+    ptr::NonNull,
+    sync::{Arc, Mutex, PoisonError},
+};
 
-use crate::{Extractable, extractor::Extractor};
+use crate::extractor::Extractor;
 
 impl Drop for EntityData {
     fn drop(&mut self) {
-        unsafe { (self.extractor.dropper)(self.data) };
+        #[cfg(feature = "serde")]
+        if !*self.ready.get_mut() {
+            // SAFETY: The data was allocated by `new_uninit` and never initialized.
+            unsafe { self.dealloc_uninit() };
+            return;
+        }
+
+        let hooks = std::mem::take(
+            self.destroy_hooks
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        // Drops the data even if a hook panics.
+        let _dropper = Dropper(self);
+        for hook in hooks {
+            hook(self.data);
+        }
+        // SAFETY: No strong reference is left, so nothing else can access the data.
+        unsafe { self.extractor.destroy_hooks.run(self.data) };
+    }
+}
+
+struct Dropper<'a>(&'a EntityData);
+
+impl Drop for Dropper<'_> {
+    fn drop(&mut self) {
+        // SAFETY: The data is initialized, and dropped only here.
+        unsafe { (self.0.extractor.dropper)(self.0.data) };
     }
 }
 
+/// A hook registered for one entity, called with its data.
+pub(crate) type InstanceHook = Box<dyn FnOnce(NonNull<u8>) + Send>;
+
 unsafe impl Send for EntityData {}
 unsafe impl Sync for EntityData {}
 
-#[derive(Clone)]
 pub struct EntityData {
     /// Pointer to the entity data
     pub(crate) data: NonNull<u8>,
 
     /// Extractor for component access
     pub(crate) extractor: &'static Extractor,
+
+    /// Lock guarding the whole entity, shared by every extracted component
+    #[cfg(feature = "locking")]
+    pub(crate) lock: parking_lot::RwLock<()>,
+
+    /// Whether the entity was created with `Acquirable::new_locked`, so its components
+    /// may be written and are only accessed through the lock
+    #[cfg(feature = "locking")]
+    pub(crate) locked: bool,
+
+    /// Whether `data` is initialized; `false` only while the entity is being deserialized
+    #[cfg(feature = "serde")]
+    pub(crate) ready: std::sync::atomic::AtomicBool,
+
+    /// Hooks registered with `Acquirable::on_destroy`, called before the data is dropped
+    pub(crate) destroy_hooks: Mutex<Vec<InstanceHook>>,
+
+    /// Identity of the entity, unique for the lifetime of the process
+    #[cfg(feature = "entity-id")]
+    pub(crate) id: EntityId,
+}
+
+/// A process-wide unique identifier of an entity.
+///
+/// IDs are allocated in increasing order when an entity is created and never reused,
+/// unlike the address compared by [`Acquirable::ptr_eq`](crate::Acquirable::ptr_eq).
+/// They are not preserved by serialization: a deserialized entity gets a new ID.
+#[cfg(feature = "entity-id")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntityId(std::num::NonZeroU64);
+
+#[cfg(feature = "entity-id")]
+impl EntityId {
+    fn next() -> Self {
+        static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let id = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Self(std::num::NonZeroU64::MIN.saturating_add(id))
+    }
+
+    /// The ID as an integer, starting from 1.
+    #[inline(always)]
+    pub fn get(self) -> u64 {
+        self.0.get()
+    }
+}
+
+#[cfg(feature = "entity-id")]
+impl std::fmt::Display for EntityId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
 }
 
 impl EntityData {
@@ -26,20 +109,135 @@ impl EntityData {
         Self {
             data: unsafe { NonNull::new_unchecked(ptr) },
             extractor,
+            #[cfg(feature = "locking")]
+            lock: parking_lot::RwLock::new(()),
+            #[cfg(feature = "locking")]
+            locked: false,
+            #[cfg(feature = "serde")]
+            ready: std::sync::atomic::AtomicBool::new(true),
+            destroy_hooks: Mutex::new(Vec::new()),
+            #[cfg(feature = "entity-id")]
+            id: EntityId::next(),
+        }
+    }
+
+    /// Allocate an entity of the extractor's concrete type without initializing it.
+    ///
+    /// The entity is not [`ready`](Self::is_ready) until the data is written and
+    /// [`set_ready`](Self::set_ready) is called. Until then, dropping it only frees the
+    /// allocation.
+    #[cfg(feature = "serde")]
+    pub(crate) fn new_uninit(extractor: &'static Extractor) -> Self {
+        // SAFETY: The size and alignment were taken from a real type.
+        let layout = unsafe {
+            std::alloc::Layout::from_size_align_unchecked(extractor.size, extractor.align)
+        };
+        let data = if layout.size() == 0 {
+            // SAFETY: Alignments are non-zero. This matches the pointer `Box` uses for
+            // zero-sized types.
+            unsafe { NonNull::new_unchecked(std::ptr::without_provenance_mut(layout.align())) }
+        } else {
+            // SAFETY: The layout has a non-zero size.
+            let ptr = unsafe { std::alloc::alloc(layout) };
+            NonNull::new(ptr).unwrap_or_else(|| std::alloc::handle_alloc_error(layout))
+        };
+        Self {
+            data,
+            extractor,
+            #[cfg(feature = "locking")]
+            lock: parking_lot::RwLock::new(()),
+            #[cfg(feature = "locking")]
+            locked: false,
+            ready: std::sync::atomic::AtomicBool::new(false),
+            destroy_hooks: Mutex::new(Vec::new()),
+            #[cfg(feature = "entity-id")]
+            id: EntityId::next(),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline(always)]
+    pub(crate) fn is_ready(&self) -> bool {
+        self.ready.load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    /// Mark the data written since [`new_uninit`](Self::new_uninit) as initialized.
+    ///
+    /// # Safety
+    /// `data` must hold a valid value of the extractor's concrete type.
+    #[cfg(feature = "serde")]
+    pub(crate) unsafe fn set_ready(&self) {
+        self.ready.store(true, std::sync::atomic::Ordering::Release);
+    }
+
+    /// # Safety
+    /// `data` must have been allocated by `new_uninit` and must not be initialized.
+    #[cfg(feature = "serde")]
+    unsafe fn dealloc_uninit(&mut self) {
+        if self.extractor.size != 0 {
+            // SAFETY: Same layout as the allocation in `new_uninit`.
+            unsafe {
+                std::alloc::dealloc(
+                    self.data.as_ptr(),
+                    std::alloc::Layout::from_size_align_unchecked(
+                        self.extractor.size,
+                        self.extractor.align,
+                    ),
+                )
+            };
         }
     }
 
     #[inline(always)]
-    pub(crate) fn extract<T: Extractable>(self: &Arc<Self>) -> Option<crate::Acquirable<T>> {
+    pub(crate) fn extract<T: 'static>(self: &Arc<Self>) -> Option<crate::Acquirable<T>> {
         // SAFETY: extract_ptr validates the type through the Extractor
         let extracted = unsafe { self.extract_ptr::<T>()? };
         Some(crate::Acquirable::new_raw(extracted, self.clone()))
     }
 
+    #[inline]
+    pub(crate) fn extract_all<T: 'static>(self: &Arc<Self>) -> Vec<crate::Acquirable<T>> {
+        #[cfg(feature = "locking")]
+        let _guard = self.variant_guard();
+        // SAFETY: extract_all_ptr only yields offsets the Extractor recorded for type T,
+        // and enum variants cannot change while the entity is read-locked.
+        unsafe { self.extractor.extract_all_ptr::<T>(self.data) }
+            .map(|extracted| crate::Acquirable::new_raw(extracted, self.clone()))
+            .collect()
+    }
+
     #[inline(always)]
     pub(crate) unsafe fn extract_ptr<T: 'static>(&self) -> Option<NonNull<T>> {
+        #[cfg(feature = "locking")]
+        let _guard = self.variant_guard();
         // SAFETY: The caller must ensure proper synchronization. The extractor validates
         // that type T exists in the entity data and returns None if not present.
         unsafe { self.extractor.extract_ptr::<T>(self.data) }
     }
+
+    /// Find the components containing `target`, from the concrete type down to `target`.
+    pub(crate) fn path_to<T: 'static>(
+        &self,
+        target: NonNull<T>,
+    ) -> Option<Vec<crate::extractor::PathSegment>> {
+        #[cfg(feature = "locking")]
+        let _guard = self.variant_guard();
+        // SAFETY: The data is live while `self` is, and enum variants cannot change
+        // while the entity is read-locked.
+        unsafe {
+            self.extractor
+                .path_to(self.data, std::any::TypeId::of::<T>(), target.cast())
+        }
+    }
+
+    /// Read-lock the entity if looking up a component may read an enum discriminant.
+    ///
+    /// The lock is taken recursively so extraction works while holding a read guard.
+    /// Extracting from an entity containing enums while holding its write guard on the
+    /// same thread deadlocks.
+    #[cfg(feature = "locking")]
+    #[inline(always)]
+    fn variant_guard(&self) -> Option<parking_lot::RwLockReadGuard<'_, ()>> {
+        (!self.extractor.variants.is_empty()).then(|| self.lock.read_recursive())
+    }
 }
@@ -5,30 +5,184 @@ use rustc_hash::FxHashMap;
 /// Trait for types that can be extracted from entity data.
 ///
 /// This is typically derived using `#[derive(Extractable)]`.
+///
+/// # Derive errors
+///
+/// Misuse of the `#[extractable]` attributes is reported on the offending tokens.
+/// Unknown options are rejected:
+///
+/// ```compile_fail
+/// use structecs::*;
+///
+/// #[derive(Extractable)]
+/// #[extractable(allow_duplicate = true)]
+/// struct Entity {
+///     id: u32,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use structecs::*;
+///
+/// #[derive(Extractable)]
+/// struct Player {
+///     #[extractable(flat)]
+///     id: u32,
+/// }
+/// ```
+///
+/// So are fields that do not exist:
+///
+/// ```compile_fail
+/// use structecs::*;
+///
+/// #[derive(Extractable)]
+/// #[extractable(entity)]
+/// struct Player {
+///     id: u32,
+/// }
+/// ```
+///
+/// A field takes a single extraction attribute, and a skipped field cannot be listed
+/// on the struct:
+///
+/// ```compile_fail
+/// use structecs::*;
+///
+/// #[derive(Extractable)]
+/// struct Entity {
+///     id: u32,
+/// }
+///
+/// #[derive(Extractable)]
+/// struct Player {
+///     #[extractable(flatten)]
+///     #[extractable(skip)]
+///     entity: Entity,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use structecs::*;
+///
+/// #[derive(Extractable)]
+/// struct Entity {
+///     id: u32,
+/// }
+///
+/// #[derive(Extractable)]
+/// #[extractable(entity)]
+/// struct Player {
+///     #[extractable(skip)]
+///     entity: Entity,
+/// }
+/// ```
+///
+/// Serde tags cannot be given to generic types, since a tag names one instantiation:
+///
+/// ```compile_fail
+/// use structecs::*;
+///
+/// #[derive(Extractable, serde::Serialize, serde::Deserialize)]
+/// #[extractable(tag = "wrapper")]
+/// struct Wrapper<T> {
+///     value: T,
+/// }
+/// ```
 pub trait Extractable: 'static + Sized {
     /// Metadata describing how to extract components from this type.
     const METADATA_LIST: &'static [ExtractionMetadata];
-    #[cfg(debug_assertions)]
+    /// The module path and name of the type, used to check containment at compile time
+    /// (see [`ExtractionMetadata::is_has`]).
+    ///
+    /// The derive appends `<>` for generic types, which excludes them from the
+    /// compile-time duplicate check (see [`ExtractionMetadata::has_duplicates`]).
     const IDENTIFIER: &'static str;
+    /// Whether this type submits an [`ExtractableType`] to the inventory.
+    ///
+    /// The derive sets this to `false` for generic types, whose extractors are built
+    /// on first use. Debug builds panic when a type claiming registration is missing
+    /// from the inventory; release builds fall back to building it on demand.
+    const REGISTERED: bool = true;
+    /// Whether the same component type may be reachable at several offsets.
+    ///
+    /// Duplicates are rejected by default because [`extract`](crate::Acquirable::extract)
+    /// could only ever return one of them: at compile time when the fields name the
+    /// same type, otherwise with a panic when an entity of the type is first created.
+    /// When allowed, `extract` returns the first occurrence in field declaration order,
+    /// and
+    /// [`extract_all`](crate::Acquirable::extract_all) returns every occurrence.
+    ///
+    /// ```compile_fail
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Health {
+    ///     current: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(main_hand, off_hand)]
+    /// struct Hands {
+    ///     main_hand: Health,
+    ///     off_hand: Health,
+    /// }
+    /// ```
+    const ALLOW_DUPLICATES: bool = false;
+    /// Called with the entity when the last strong reference to an entity of this
+    /// concrete type is dropped, right before the entity itself is dropped.
+    ///
+    /// Set by the derive with `#[extractable(on_destroy = path::to::function)]`.
+    const ON_DESTROY: Option<fn(&mut Self)> = None;
+    /// Trait objects this type can be extracted as with
+    /// [`extract_dyn`](crate::Acquirable::extract_dyn).
+    ///
+    /// Set by the derive with `#[extractable(dyn Trait)]`.
+    const DYN_METADATA_LIST: &'static [DynMetadata] = &[];
 }
 
 pub struct ExtractableType {
     pub type_id: TypeId,
+    pub type_name: fn() -> &'static str,
+    pub size: usize,
+    pub align: usize,
     pub metadata: &'static [ExtractionMetadata],
+    pub allow_duplicates: bool,
+    pub dyn_metadata: &'static [DynMetadata],
     pub dropper: unsafe fn(NonNull<u8>),
 }
 
 impl ExtractableType {
+    /// Describe the extractable type `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics (at compile time when evaluated in a const context) if `T` contains the
+    /// same component type more than once and does not allow it.
     pub const fn new<T: Extractable>() -> Self {
+        if !T::ALLOW_DUPLICATES && ExtractionMetadata::has_duplicates(T::METADATA_LIST) {
+            panic!(
+                "Extractable type contains the same component type at several offsets, \
+                 so extracting it would be ambiguous. Register only one of the fields \
+                 containing it, or opt in with #[extractable(allow_duplicates = true)] \
+                 and use `extract_all`."
+            );
+        }
         Self {
             type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>,
+            size: size_of::<T>(),
+            align: align_of::<T>(),
             metadata: T::METADATA_LIST,
+            allow_duplicates: T::ALLOW_DUPLICATES,
+            dyn_metadata: T::DYN_METADATA_LIST,
             dropper: |data_ptr: NonNull<u8>| {
                 // SAFETY: The caller guarantees that data_ptr points to a valid instance of T.
-                unsafe {
-                    let boxed: Box<T> = Box::from_raw(data_ptr.as_ptr() as *mut T);
-                    drop(boxed);
+                let mut boxed: Box<T> = unsafe { Box::from_raw(data_ptr.as_ptr() as *mut T) };
+                if let Some(on_destroy) = T::ON_DESTROY {
+                    on_destroy(&mut boxed);
                 }
+                drop(boxed);
             },
         }
     }
@@ -36,23 +190,82 @@ impl ExtractableType {
 
 inventory::collect!(ExtractableType);
 
+/// A trait object type implemented by an extractable type, with the cast producing it.
+pub struct DynMetadata {
+    type_id: TypeId,
+    /// A `fn(NonNull<u8>) -> NonNull<D>` for the `D` identified by `type_id`.
+    cast: fn(),
+}
+
+impl DynMetadata {
+    /// Create metadata for the trait object type `D`.
+    ///
+    /// # Safety
+    ///
+    /// `cast` is only called with a pointer to the type this metadata belongs to, and
+    /// must return the same pointer unsized to `D`.
+    #[inline]
+    pub const unsafe fn new<D: ?Sized + 'static>(cast: fn(NonNull<u8>) -> NonNull<D>) -> Self {
+        Self {
+            type_id: TypeId::of::<D>(),
+            // SAFETY: Function pointers have the same layout regardless of their
+            // signature; `cast` transmutes it back only for the same `D`.
+            cast: unsafe { std::mem::transmute::<fn(NonNull<u8>) -> NonNull<D>, fn()>(cast) },
+        }
+    }
+
+    /// The `TypeId` of the trait object type.
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Get the cast to `D`, if this metadata was created for `D`.
+    #[inline]
+    pub fn cast<D: ?Sized + 'static>(&self) -> Option<fn(NonNull<u8>) -> NonNull<D>> {
+        (self.type_id == TypeId::of::<D>()).then(|| {
+            // SAFETY: Created from a `fn(NonNull<u8>) -> NonNull<D>` for the same `D`.
+            unsafe { std::mem::transmute::<fn(), fn(NonNull<u8>) -> NonNull<D>>(self.cast) }
+        })
+    }
+}
+
 /// Metadata describing how to extract types from an entity structure.
 pub enum ExtractionMetadata {
     /// Direct target at a specific offset.
     Target {
         type_id: TypeId,
+        type_name: fn() -> &'static str,
         offset: usize,
+        /// The field holding the target, empty for the type itself.
+        field: &'static str,
 
-        #[cfg(debug_assertions)]
         identifier: &'static str,
     },
     /// Nested extractable type with its own metadata.
     Nested {
         type_id: TypeId,
+        type_name: fn() -> &'static str,
         offset: usize,
         nested: &'static [ExtractionMetadata],
+        /// The field holding the nested type.
+        field: &'static str,
+
+        identifier: &'static str,
+    },
+    /// Extractable type stored in an enum variant, present only while the enum holds
+    /// that variant.
+    ///
+    /// `locate` receives a pointer to the enum and returns a pointer to the variant's
+    /// field if the enum currently holds the variant. `nested` is relative to the field.
+    Variant {
+        type_id: TypeId,
+        type_name: fn() -> &'static str,
+        locate: unsafe fn(NonNull<u8>) -> Option<NonNull<u8>>,
+        nested: &'static [ExtractionMetadata],
+        /// The variant and its field, such as `Stone.0`.
+        field: &'static str,
 
-        #[cfg(debug_assertions)]
         identifier: &'static str,
     },
 }
@@ -63,12 +276,30 @@ impl ExtractionMetadata {
     pub const fn new<T: Extractable>(offset: usize) -> Self {
         Self::Target {
             type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>,
             offset,
-            #[cfg(debug_assertions)]
+            field: "",
             identifier: T::IDENTIFIER,
         }
     }
 
+    /// Create metadata for a field of any `'static` type, extractable as a leaf.
+    ///
+    /// Unlike [`new`](Self::new), `T` does not need to implement [`Extractable`], so
+    /// this is used for fields marked `#[extract]`. `identifier` is the field's type as
+    /// written, used for debugging only; it never matches an `Extractable::IDENTIFIER`,
+    /// so such fields are not visible to the compile-time checks.
+    #[inline]
+    pub const fn new_field<T: 'static>(offset: usize, identifier: &'static str) -> Self {
+        Self::Target {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>,
+            offset,
+            field: "",
+            identifier,
+        }
+    }
+
     /// Create metadata for a nested extractable type.
     #[inline]
     pub const fn new_nested<T: crate::Extractable>(
@@ -77,13 +308,103 @@ impl ExtractionMetadata {
     ) -> Self {
         Self::Nested {
             type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>,
             offset,
             nested,
-            #[cfg(debug_assertions)]
+            field: "",
+            identifier: T::IDENTIFIER,
+        }
+    }
+
+    /// Create metadata for a field of an enum variant.
+    ///
+    /// `nested` is `T::METADATA_LIST` to make the types nested in `T` extractable as
+    /// well, or empty to extract only `T` itself.
+    ///
+    /// # Safety contract
+    ///
+    /// `locate` must only be called with a pointer to the enum this metadata belongs to,
+    /// and must return either `None` or a pointer to a `T` inside that enum.
+    #[inline]
+    pub const fn new_variant<T: Extractable>(
+        locate: unsafe fn(NonNull<u8>) -> Option<NonNull<u8>>,
+        nested: &'static [ExtractionMetadata],
+    ) -> Self {
+        Self::Variant {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>,
+            locate,
+            nested,
+            field: "",
             identifier: T::IDENTIFIER,
         }
     }
 
+    /// Create metadata for a field of an enum variant of any `'static` type, extractable
+    /// as a leaf.
+    ///
+    /// This is the enum counterpart of [`new_field`](Self::new_field), with the same
+    /// contract on `locate` as [`new_variant`](Self::new_variant).
+    #[inline]
+    pub const fn new_variant_field<T: 'static>(
+        locate: unsafe fn(NonNull<u8>) -> Option<NonNull<u8>>,
+        identifier: &'static str,
+    ) -> Self {
+        Self::Variant {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>,
+            locate,
+            nested: &[],
+            field: "",
+            identifier,
+        }
+    }
+
+    /// Set the name of the field this entry was created for.
+    ///
+    /// Used by [`Acquirable::path`](crate::Acquirable::path). The derive names every
+    /// field; entries left unnamed are reported with an empty name.
+    #[inline]
+    pub const fn with_field(mut self, name: &'static str) -> Self {
+        match &mut self {
+            ExtractionMetadata::Target { field, .. }
+            | ExtractionMetadata::Nested { field, .. }
+            | ExtractionMetadata::Variant { field, .. } => *field = name,
+        }
+        self
+    }
+
+    /// The `TypeId` of the type extracted by this entry.
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        match self {
+            ExtractionMetadata::Target { type_id, .. }
+            | ExtractionMetadata::Nested { type_id, .. }
+            | ExtractionMetadata::Variant { type_id, .. } => *type_id,
+        }
+    }
+
+    /// The name of the type extracted by this entry, as returned by
+    /// [`std::any::type_name`].
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ExtractionMetadata::Target { type_name, .. }
+            | ExtractionMetadata::Nested { type_name, .. }
+            | ExtractionMetadata::Variant { type_name, .. } => type_name(),
+        }
+    }
+
+    /// The name of the field this entry was created for, empty for the type itself.
+    #[inline]
+    pub fn field(&self) -> &'static str {
+        match self {
+            ExtractionMetadata::Target { field, .. }
+            | ExtractionMetadata::Nested { field, .. }
+            | ExtractionMetadata::Variant { field, .. } => field,
+        }
+    }
+
     /// Compile-time check whether `List` contains `Target` in its extraction metadata.
     ///
     /// This function is designed to be called at the beginning of functions that may panic.
@@ -97,6 +418,15 @@ impl ExtractionMetadata {
     /// - `type_name()` is also not const-stable
     /// - String comparison with `IDENTIFIER` (module_path + struct_name) provides
     ///   the same uniqueness guarantee while being const-evaluable
+    ///
+    /// The identifier does not include generic arguments, so all instantiations of a
+    /// generic type (e.g. `Tagged<A>` and `Tagged<B>`) compare as the same type here.
+    ///
+    /// Types stored in enum variants are not considered contained, since they are
+    /// only present while the enum holds the variant.
+    ///
+    /// The check is available in every build profile, so the checked APIs reject a
+    /// type that does not contain the target in release builds as well.
     pub const fn is_has<List: Extractable, Target: Extractable>() -> bool {
         let list = List::METADATA_LIST;
         let target = Target::IDENTIFIER;
@@ -110,24 +440,7 @@ impl ExtractionMetadata {
         false
     }
 
-    #[cfg(debug_assertions)]
     pub const fn has_val(&self, identifier: &str) -> bool {
-        const fn eq_str(a: &str, b: &str) -> bool {
-            let a_bytes = a.as_bytes();
-            let b_bytes = b.as_bytes();
-            if a_bytes.len() != b_bytes.len() {
-                return false;
-            }
-            let mut idx = 0;
-            while idx < a_bytes.len() {
-                if a_bytes[idx] != b_bytes[idx] {
-                    return false;
-                }
-                idx += 1;
-            }
-            true
-        }
-
         match self {
             ExtractionMetadata::Target { identifier: id, .. } => eq_str(id, identifier),
             ExtractionMetadata::Nested {
@@ -148,12 +461,116 @@ impl ExtractionMetadata {
                     false
                 }
             }
+            ExtractionMetadata::Variant { .. } => false,
+        }
+    }
+
+    /// Const check whether any type in `list` is reachable at more than one offset.
+    ///
+    /// A nested type always appears twice at the same offset (as the `Nested` entry and
+    /// as the first entry of its own list), which is not considered a duplicate. Enum
+    /// variants are ignored, as only one of them is present at a time.
+    ///
+    /// Generic types, and everything nested in them, are skipped as well: their
+    /// identifiers are the same for every instantiation, so `Tagged<A>` and
+    /// `Tagged<B>` cannot be told apart here. The extractor checks them by `TypeId`
+    /// when the type is first used.
+    pub const fn has_duplicates(list: &[ExtractionMetadata]) -> bool {
+        const fn walk(
+            root: &[ExtractionMetadata],
+            list: &[ExtractionMetadata],
+            base: usize,
+        ) -> bool {
+            let mut idx = 0;
+            while list.len() > idx {
+                let Some((identifier, offset)) = list[idx].identifier_and_offset() else {
+                    idx += 1;
+                    continue;
+                };
+                if is_generic(identifier) {
+                    idx += 1;
+                    continue;
+                }
+                if occurs_elsewhere(root, 0, identifier, base + offset) {
+                    return true;
+                }
+                if let ExtractionMetadata::Nested { nested, .. } = &list[idx]
+                    && walk(root, nested, base + offset)
+                {
+                    return true;
+                }
+                idx += 1;
+            }
+            false
+        }
+
+        const fn occurs_elsewhere(
+            list: &[ExtractionMetadata],
+            base: usize,
+            identifier: &str,
+            at: usize,
+        ) -> bool {
+            let mut idx = 0;
+            while list.len() > idx {
+                let Some((own, offset)) = list[idx].identifier_and_offset() else {
+                    idx += 1;
+                    continue;
+                };
+                if is_generic(own) {
+                    idx += 1;
+                    continue;
+                }
+                if base + offset != at && eq_str(own, identifier) {
+                    return true;
+                }
+                if let ExtractionMetadata::Nested { nested, .. } = &list[idx]
+                    && occurs_elsewhere(nested, base + offset, identifier, at)
+                {
+                    return true;
+                }
+                idx += 1;
+            }
+            false
+        }
+
+        // Identifiers of generic types end with `<>`, see `Extractable::IDENTIFIER`.
+        const fn is_generic(identifier: &str) -> bool {
+            let bytes = identifier.as_bytes();
+            bytes.len() >= 2 && bytes[bytes.len() - 2] == b'<' && bytes[bytes.len() - 1] == b'>'
+        }
+
+        walk(list, list, 0)
+    }
+
+    /// The identifier and offset of entries at a fixed offset; `None` for enum variants.
+    const fn identifier_and_offset(&self) -> Option<(&'static str, usize)> {
+        match self {
+            ExtractionMetadata::Target {
+                identifier, offset, ..
+            }
+            | ExtractionMetadata::Nested {
+                identifier, offset, ..
+            } => Some((identifier, *offset)),
+            ExtractionMetadata::Variant { .. } => None,
         }
     }
 
     /// Flatten nested metadata into a single HashMap of type -> offset mappings.
+    ///
+    /// A type reachable at several offsets maps to its first occurrence in field
+    /// declaration order. Types stored in enum variants are not included, since their
+    /// offset depends on the variant held.
     #[inline]
     pub fn flatten(list: &[ExtractionMetadata]) -> FxHashMap<TypeId, usize> {
+        Self::flatten_all(list)
+            .into_iter()
+            .map(|(type_id, offsets)| (type_id, offsets[0]))
+            .collect()
+    }
+
+    /// Flatten nested metadata into every distinct offset of each type, in field
+    /// declaration order.
+    pub fn flatten_all(list: &[ExtractionMetadata]) -> FxHashMap<TypeId, Vec<usize>> {
         let mut result = FxHashMap::default();
         Self::flatten_internal(list, 0, &mut result);
         result
@@ -162,25 +579,53 @@ impl ExtractionMetadata {
     fn flatten_internal(
         list: &[ExtractionMetadata],
         base_offset: usize,
-        result: &mut FxHashMap<TypeId, usize>,
+        result: &mut FxHashMap<TypeId, Vec<usize>>,
     ) {
         for metadata in list {
-            match metadata {
+            let (type_id, offset) = match metadata {
                 ExtractionMetadata::Target {
                     type_id, offset, ..
-                } => {
-                    result.insert(*type_id, base_offset + *offset);
-                }
-                ExtractionMetadata::Nested {
-                    type_id,
-                    offset,
-                    nested,
-                    ..
-                } => {
-                    result.insert(*type_id, base_offset + *offset);
-                    Self::flatten_internal(nested, base_offset + *offset, result);
                 }
+                | ExtractionMetadata::Nested {
+                    type_id, offset, ..
+                } => (*type_id, base_offset + *offset),
+                ExtractionMetadata::Variant { .. } => continue,
+            };
+            let offsets = result.entry(type_id).or_default();
+            if !offsets.contains(&offset) {
+                offsets.push(offset);
+            }
+            if let ExtractionMetadata::Nested { nested, .. } = metadata {
+                Self::flatten_internal(nested, offset, result);
             }
         }
     }
 }
+
+/// Report a containment the compile-time check accepted but the extractor rejects,
+/// which happens for different instantiations of the same generic type.
+#[cold]
+#[track_caller]
+pub(crate) fn mismatch<Container, Target>() -> ! {
+    panic!(
+        "`{}` does not contain `{}` as an extractable component",
+        std::any::type_name::<Container>(),
+        std::any::type_name::<Target>()
+    )
+}
+
+const fn eq_str(a: &str, b: &str) -> bool {
+    let a_bytes = a.as_bytes();
+    let b_bytes = b.as_bytes();
+    if a_bytes.len() != b_bytes.len() {
+        return false;
+    }
+    let mut idx = 0;
+    while idx < a_bytes.len() {
+        if a_bytes[idx] != b_bytes[idx] {
+            return false;
+        }
+        idx += 1;
+    }
+    true
+}
@@ -1,33 +1,687 @@
-use std::{any::TypeId, ptr::NonNull};
+use std::{
+    any::TypeId,
+    collections::VecDeque,
+    ptr::NonNull,
+    sync::{
+        Arc, PoisonError, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
 use rustc_hash::FxHashMap;
 
-use crate::{ExtractionMetadata, extractable::ExtractableType};
+use crate::{
+    ExtractionMetadata,
+    extractable::{DynMetadata, ExtractableType},
+};
 
 /// Extracts components from entity data using pre-computed offsets.
+///
+/// One `Extractor` exists per concrete extractable type. It can be inspected at
+/// runtime through the [`registry`](crate::registry) functions.
 pub struct Extractor {
+    pub(crate) type_id: TypeId,
+    pub(crate) type_name: &'static str,
+    pub(crate) size: usize,
+    pub(crate) align: usize,
+    pub(crate) metadata: &'static [ExtractionMetadata],
     pub(crate) offsets: FxHashMap<TypeId, usize>,
+    /// Names of the types in `offsets`.
+    pub(crate) type_names: FxHashMap<TypeId, &'static str>,
+    /// Every offset of the types reachable at more than one offset.
+    pub(crate) duplicates: FxHashMap<TypeId, Box<[usize]>>,
+    /// Enum variants whose fields are looked up per entity, on a miss in `offsets`.
+    pub(crate) variants: Box<[VariantExtractor]>,
+    /// Trait objects the concrete type can be extracted as.
+    pub(crate) dyn_metadata: &'static [DynMetadata],
     pub(crate) dropper: unsafe fn(NonNull<u8>),
+    /// Hooks registered with [`registry::on_destroy`](crate::registry::on_destroy).
+    pub(crate) destroy_hooks: DestroyHooks,
+    /// Set when the concrete type contains duplicated components without opting in.
+    pub(crate) duplicate_error: Option<String>,
+}
+
+#[cold]
+#[inline(never)]
+fn duplicate_panic(error: &str) -> ! {
+    panic!("{error}")
 }
 
 impl Extractor {
     pub(crate) fn new_type(target: &ExtractableType) -> Self {
+        let type_name = (target.type_name)();
+        let all_offsets = ExtractionMetadata::flatten_all(target.metadata);
+        let offsets = all_offsets
+            .iter()
+            .map(|(type_id, offsets)| (*type_id, offsets[0]))
+            .collect();
+        let duplicates: FxHashMap<TypeId, Box<[usize]>> = all_offsets
+            .into_iter()
+            .filter(|(_, offsets)| offsets.len() > 1)
+            .map(|(type_id, offsets)| (type_id, offsets.into_boxed_slice()))
+            .collect();
+
+        // The const check in `ExtractableType::new` compares identifiers, which do not
+        // cover `#[extract]` fields spelled differently; `TypeId`s catch the rest. The
+        // error is only raised once the type is used, so building the global cache
+        // never fails for the other types.
+        let duplicate_error = if !target.allow_duplicates
+            && let Some(type_id) = duplicates.keys().next()
+        {
+            let mut fields = Vec::new();
+            collect_paths(
+                target.metadata,
+                target.type_id,
+                *type_id,
+                &mut Vec::new(),
+                false,
+                &mut fields,
+            );
+            let fields: Vec<String> = fields
+                .into_iter()
+                .filter(|(_, in_variant)| !in_variant)
+                .map(|(path, _)| format!("`{}`", path.join(".")))
+                .collect();
+            Some(format!(
+                "Extractable type `{}` contains the same component type at several offsets \
+                 ({}). Register only one of the fields containing it, or opt in with \
+                 #[extractable(allow_duplicates = true)] and use `extract_all`.",
+                type_name,
+                fields.join(", ")
+            ))
+        } else {
+            None
+        };
+
         Self {
-            offsets: ExtractionMetadata::flatten(target.metadata),
+            type_id: target.type_id,
+            type_name,
+            size: target.size,
+            align: target.align,
+            metadata: target.metadata,
+            offsets,
+            type_names: collect_type_names(target.metadata),
+            duplicates,
+            variants: VariantExtractor::collect(target.metadata),
+            dyn_metadata: target.dyn_metadata,
             dropper: target.dropper,
+            destroy_hooks: DestroyHooks::default(),
+            duplicate_error,
+        }
+    }
+
+    /// Panic if the concrete type contains duplicated components it did not opt in to.
+    #[inline]
+    pub(crate) fn check_duplicates(&self) {
+        if let Some(error) = &self.duplicate_error {
+            duplicate_panic(error);
+        }
+    }
+
+    /// The `TypeId` of the concrete type this extractor was built for.
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// The name of the concrete type, as returned by [`std::any::type_name`].
+    #[inline]
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// The size of the concrete type in bytes.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The alignment of the concrete type in bytes.
+    #[inline]
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// Iterate over every type extractable from the concrete type, with its name, as
+    /// returned by [`std::any::type_name`], and its byte offset.
+    ///
+    /// This includes the concrete type itself at offset `0`. Types stored in enum
+    /// variants have no fixed offset and are not included.
+    pub fn components(&self) -> impl Iterator<Item = (TypeId, &'static str, usize)> + '_ {
+        self.offsets.iter().map(|(type_id, offset)| {
+            let type_name = self.type_names.get(type_id).copied().unwrap_or_default();
+            (*type_id, type_name, *offset)
+        })
+    }
+
+    /// Get the byte offset of `type_id` within the concrete type, if it is extractable
+    /// at a fixed offset.
+    #[inline]
+    pub fn offset_of(&self, type_id: TypeId) -> Option<usize> {
+        self.offsets.get(&type_id).copied()
+    }
+
+    /// Get every byte offset of `type_id` within the concrete type, in field declaration order.
+    ///
+    /// This has more than one element only for types allowing duplicates.
+    pub fn all_offsets_of(&self, type_id: TypeId) -> &[usize] {
+        if let Some(offsets) = self.duplicates.get(&type_id) {
+            return offsets;
+        }
+        self.offsets
+            .get(&type_id)
+            .map(std::slice::from_ref)
+            .unwrap_or_default()
+    }
+
+    /// Get the names of the fields leading to the occurrence of `type_id` that
+    /// [`extract`](crate::Acquirable::extract) returns from every value of the
+    /// concrete type.
+    ///
+    /// The path is empty for the concrete type itself. Returns `None` if `type_id` is
+    /// not at a fixed offset; see [`field_paths`](Self::field_paths) for types stored
+    /// in enum variants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::any::TypeId;
+    /// use structecs::*;
+    ///
+    /// #[derive(Extractable)]
+    /// struct Health {
+    ///     value: u32,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(health)]
+    /// struct LivingEntity {
+    ///     health: Health,
+    /// }
+    ///
+    /// #[derive(Extractable)]
+    /// #[extractable(living)]
+    /// struct Player {
+    ///     living: LivingEntity,
+    /// }
+    ///
+    /// let player = registry::find_type(TypeId::of::<Player>()).unwrap();
+    /// let path = player.field_path(TypeId::of::<Health>()).unwrap();
+    /// assert_eq!(path.join("."), "living.health");
+    /// ```
+    pub fn field_path(&self, type_id: TypeId) -> Option<Vec<&'static str>> {
+        if self.type_id == type_id {
+            return Some(Vec::new());
+        }
+        let mut paths = Vec::new();
+        collect_paths(
+            self.metadata,
+            self.type_id,
+            type_id,
+            &mut Vec::new(),
+            false,
+            &mut paths,
+        );
+        paths
+            .into_iter()
+            .find(|(_, in_variant)| !in_variant)
+            .map(|(path, _)| path)
+    }
+
+    /// Get the names of the fields leading to every occurrence of `type_id`, in field
+    /// declaration order.
+    ///
+    /// This includes occurrences in enum variants, present only while the enum holds
+    /// the variant. Fields of enum variants are named after the variant, such as
+    /// `Stone.0`.
+    pub fn field_paths(&self, type_id: TypeId) -> Vec<Vec<&'static str>> {
+        let mut paths = Vec::new();
+        if self.type_id == type_id {
+            paths.push((Vec::new(), false));
+        }
+        collect_paths(
+            self.metadata,
+            self.type_id,
+            type_id,
+            &mut Vec::new(),
+            false,
+            &mut paths,
+        );
+        paths.into_iter().map(|(path, _)| path).collect()
+    }
+
+    /// Check whether `type_id` can be extracted from every value of the concrete type.
+    ///
+    /// Types stored in enum variants are not included, since extracting them depends on
+    /// the variant a value holds; see [`may_contain`](Self::may_contain).
+    #[inline]
+    pub fn contains(&self, type_id: TypeId) -> bool {
+        self.offsets.contains_key(&type_id)
+    }
+
+    /// Check whether the concrete type can be extracted as the trait object type
+    /// `type_id` (such as `TypeId::of::<dyn Trait>()`) with
+    /// [`extract_dyn`](crate::Acquirable::extract_dyn).
+    pub fn implements(&self, type_id: TypeId) -> bool {
+        self.dyn_metadata
+            .iter()
+            .any(|metadata| metadata.type_id() == type_id)
+    }
+
+    /// Get the cast from a pointer to the concrete type to the trait object type `D`.
+    #[inline]
+    pub(crate) fn dyn_cast<D: ?Sized + 'static>(&self) -> Option<fn(NonNull<u8>) -> NonNull<D>> {
+        self.dyn_metadata.iter().find_map(DynMetadata::cast::<D>)
+    }
+
+    /// Check whether `type_id` can be extracted from some value of the concrete type,
+    /// including types stored in enum variants.
+    pub fn may_contain(&self, type_id: TypeId) -> bool {
+        self.contains(type_id)
+            || self
+                .variants
+                .iter()
+                .any(|variant| variant.may_contain(type_id))
+    }
+
+    /// Check whether `target` still points to a `T` present in the entity data.
+    ///
+    /// Components at a fixed offset are always present. Components stored in an enum
+    /// variant are present only while the enum still holds that variant.
+    ///
+    /// # Safety
+    /// `data` must point to a live value of the concrete type, which must not be
+    /// mutated for the duration of the call.
+    pub(crate) unsafe fn is_present<T: 'static>(
+        &self,
+        data: NonNull<u8>,
+        target: NonNull<T>,
+    ) -> bool {
+        if self.variants.is_empty() {
+            return true;
+        }
+        let target = target.cast::<u8>();
+        // SAFETY: Forwarded from the caller.
+        unsafe { self.extract_all_ptr::<T>(data) }.any(|ptr| ptr.cast::<u8>() == target)
+    }
+
+    /// Extract pointers to every occurrence of a component of type T from entity data.
+    ///
+    /// # Safety
+    /// The caller must ensure the pointers are used correctly and not outlive the entity data.
+    /// Occurrences at a fixed offset come first, followed by those in the enum variants
+    /// currently held.
+    #[inline]
+    pub(crate) unsafe fn extract_all_ptr<T: 'static>(
+        &self,
+        data: NonNull<u8>,
+    ) -> impl Iterator<Item = NonNull<T>> {
+        let type_id = TypeId::of::<T>();
+        let mut located = Vec::new();
+        for variant in &self.variants {
+            // SAFETY: Forwarded from the caller.
+            unsafe { variant.locate_all(data, type_id, &mut located) };
         }
+        self.all_offsets_of(type_id)
+            .iter()
+            // SAFETY: Each offset is valid for type T and was computed during type analysis.
+            .map(move |offset| unsafe { data.add(*offset) })
+            .chain(located)
+            .map(NonNull::cast::<T>)
+    }
+
+    /// Iterate over the extractable types in breadth-first order, nearest first.
+    ///
+    /// The concrete type comes first, followed by its direct nested types, then theirs.
+    /// A type reachable through several fields may be yielded more than once. Types
+    /// stored in enum variants are skipped.
+    pub(crate) fn hierarchy(&self) -> impl Iterator<Item = TypeId> {
+        let mut queue = VecDeque::from([self.metadata]);
+        let mut current = [].iter();
+        std::iter::from_fn(move || {
+            loop {
+                if let Some(metadata) = current.next() {
+                    return Some(match metadata {
+                        ExtractionMetadata::Target { type_id, .. } => *type_id,
+                        ExtractionMetadata::Nested {
+                            type_id, nested, ..
+                        } => {
+                            queue.push_back(nested);
+                            *type_id
+                        }
+                        // Not present in every value, so never part of the hierarchy.
+                        ExtractionMetadata::Variant { .. } => continue,
+                    });
+                }
+                current = queue.pop_front()?.iter();
+            }
+        })
     }
 
     /// Extract a pointer to a component of type T from entity data.
     ///
     /// # Safety
     /// The caller must ensure the pointer is used correctly and not outlive the entity data.
+    ///
+    /// Types stored in enum variants are only found while the enum holds the variant, so
+    /// the entity data must not be mutated for the duration of the call.
     #[inline(always)]
     pub(crate) unsafe fn extract_ptr<T: 'static>(&self, data: NonNull<u8>) -> Option<NonNull<T>> {
         let type_id = const { TypeId::of::<T>() };
-        let offset = self.offsets.get(&type_id)?;
-        // SAFETY: The offset is valid for type T and was computed during type analysis.
-        // The data pointer points to the base of the entity data.
-        Some(unsafe { data.add(*offset).cast::<T>() })
+        if let Some(offset) = self.offsets.get(&type_id) {
+            // SAFETY: The offset is valid for type T and was computed during type analysis.
+            // The data pointer points to the base of the entity data.
+            return Some(unsafe { data.add(*offset).cast::<T>() });
+        }
+        if self.variants.is_empty() {
+            return None;
+        }
+        // SAFETY: Forwarded from the caller.
+        unsafe { self.locate(data, type_id) }.map(NonNull::cast::<T>)
+    }
+
+    /// Find the components containing `target`, from the concrete type down to
+    /// `target` itself.
+    ///
+    /// Returns `None` if no component of type `type_id` is present at `target`.
+    ///
+    /// # Safety
+    /// `data` must point to a live value of the concrete type, which must not be
+    /// mutated for the duration of the call.
+    pub(crate) unsafe fn path_to(
+        &self,
+        data: NonNull<u8>,
+        type_id: TypeId,
+        target: NonNull<u8>,
+    ) -> Option<Vec<PathSegment>> {
+        let mut path = vec![PathSegment {
+            type_id: self.type_id,
+            field: "",
+            ptr: data,
+        }];
+        if self.type_id == type_id && data == target {
+            return Some(path);
+        }
+        // SAFETY: Forwarded from the caller.
+        unsafe {
+            find_path(
+                self.metadata,
+                self.type_id,
+                data,
+                type_id,
+                target,
+                &mut path,
+            )
+        }
+        .then_some(path)
+    }
+
+    #[cold]
+    unsafe fn locate(&self, data: NonNull<u8>, type_id: TypeId) -> Option<NonNull<u8>> {
+        self.variants
+            .iter()
+            // SAFETY: Forwarded from the caller.
+            .find_map(|variant| unsafe { variant.locate(data, type_id) })
+    }
+}
+
+/// Map the types reachable at a fixed offset through `list` to their names.
+fn collect_type_names(list: &[ExtractionMetadata]) -> FxHashMap<TypeId, &'static str> {
+    let mut names = FxHashMap::default();
+    let mut pending = vec![list];
+    while let Some(list) = pending.pop() {
+        for metadata in list {
+            match metadata {
+                ExtractionMetadata::Target { .. } => {}
+                ExtractionMetadata::Nested { nested, .. } => pending.push(nested),
+                ExtractionMetadata::Variant { .. } => continue,
+            }
+            names.insert(metadata.type_id(), metadata.type_name());
+        }
+    }
+    names
+}
+
+/// Depth-first search of `list`, the metadata of the type `owner`, for every entry of
+/// type `type_id`. Each is appended to `out` with its field names, prefixed by `prefix`,
+/// and whether it is stored in an enum variant.
+fn collect_paths(
+    list: &[ExtractionMetadata],
+    owner: TypeId,
+    type_id: TypeId,
+    prefix: &mut Vec<&'static str>,
+    in_variant: bool,
+    out: &mut Vec<(Vec<&'static str>, bool)>,
+) {
+    for metadata in list {
+        let (nested, in_variant) = match metadata {
+            // The owner itself, already reported by the caller.
+            ExtractionMetadata::Target {
+                type_id, offset: 0, ..
+            } if *type_id == owner => continue,
+            ExtractionMetadata::Target { .. } => (None, in_variant),
+            ExtractionMetadata::Nested { nested, .. } => (Some(*nested), in_variant),
+            ExtractionMetadata::Variant { nested, .. } => (Some(*nested), true),
+        };
+        prefix.push(metadata.field());
+        if metadata.type_id() == type_id {
+            out.push((prefix.clone(), in_variant));
+        }
+        if let Some(nested) = nested {
+            collect_paths(nested, metadata.type_id(), type_id, prefix, in_variant, out);
+        }
+        prefix.pop();
+    }
+}
+
+/// A component on the way from the concrete type to an extracted component.
+pub(crate) struct PathSegment {
+    pub(crate) type_id: TypeId,
+    pub(crate) field: &'static str,
+    pub(crate) ptr: NonNull<u8>,
+}
+
+/// Depth-first search of `list`, the metadata of the type `owner` at `base`, for the
+/// entry of type `type_id` at `target`. The segments leading to it are appended to
+/// `path`.
+///
+/// # Safety
+/// `base` must point to a live `owner`, which must not be mutated for the duration of
+/// the call.
+unsafe fn find_path(
+    list: &[ExtractionMetadata],
+    owner: TypeId,
+    base: NonNull<u8>,
+    type_id: TypeId,
+    target: NonNull<u8>,
+    path: &mut Vec<PathSegment>,
+) -> bool {
+    for metadata in list {
+        let (ptr, nested) = match metadata {
+            // The owner itself, already on the path.
+            ExtractionMetadata::Target {
+                type_id, offset: 0, ..
+            } if *type_id == owner => continue,
+            // SAFETY: The offset is within the owner.
+            ExtractionMetadata::Target { offset, .. } => (unsafe { base.add(*offset) }, None),
+            ExtractionMetadata::Nested { offset, nested, .. } => {
+                // SAFETY: The offset is within the owner.
+                (unsafe { base.add(*offset) }, Some(*nested))
+            }
+            ExtractionMetadata::Variant { locate, nested, .. } => {
+                // SAFETY: `locate` belongs to the owner, which the caller keeps alive.
+                let Some(ptr) = (unsafe { locate(base) }) else {
+                    continue;
+                };
+                (ptr, Some(*nested))
+            }
+        };
+        path.push(PathSegment {
+            type_id: metadata.type_id(),
+            field: metadata.field(),
+            ptr,
+        });
+        if metadata.type_id() == type_id && ptr == target {
+            return true;
+        }
+        if let Some(nested) = nested
+            // SAFETY: `ptr` points to the live nested value.
+            && unsafe { find_path(nested, metadata.type_id(), ptr, type_id, target, path) }
+        {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+/// Lookup of the components stored in one field of an enum variant.
+pub(crate) struct VariantExtractor {
+    /// Offset of the enum within the enclosing value.
+    offset: usize,
+    /// Pointer to the field if the enum holds the variant, see [`ExtractionMetadata::Variant`].
+    locate: unsafe fn(NonNull<u8>) -> Option<NonNull<u8>>,
+    /// Components at a fixed offset within the field, including the field itself.
+    offsets: FxHashMap<TypeId, Vec<usize>>,
+    /// Enum variants nested inside the field.
+    variants: Box<[VariantExtractor]>,
+}
+
+impl VariantExtractor {
+    /// Collect the enum variants reachable from `list`, with offsets relative to `list`.
+    fn collect(list: &[ExtractionMetadata]) -> Box<[Self]> {
+        let mut result = Vec::new();
+        Self::collect_internal(list, 0, &mut result);
+        result.into_boxed_slice()
+    }
+
+    fn collect_internal(list: &[ExtractionMetadata], base_offset: usize, result: &mut Vec<Self>) {
+        for metadata in list {
+            match metadata {
+                ExtractionMetadata::Target { .. } => {}
+                ExtractionMetadata::Nested { offset, nested, .. } => {
+                    // The first entry of `nested` is the nested type itself, which is
+                    // never a variant, so recursing does not visit anything twice.
+                    Self::collect_internal(nested, base_offset + offset, result);
+                }
+                ExtractionMetadata::Variant {
+                    type_id,
+                    locate,
+                    nested,
+                    ..
+                } => {
+                    let mut offsets = ExtractionMetadata::flatten_all(nested);
+                    offsets.entry(*type_id).or_insert_with(|| vec![0]);
+                    result.push(Self {
+                        offset: base_offset,
+                        locate: *locate,
+                        offsets,
+                        variants: Self::collect(nested),
+                    });
+                }
+            }
+        }
+    }
+
+    fn may_contain(&self, type_id: TypeId) -> bool {
+        self.offsets.contains_key(&type_id)
+            || self
+                .variants
+                .iter()
+                .any(|variant| variant.may_contain(type_id))
+    }
+
+    /// Locate the variant field inside the value at `data`, if the enum holds the variant.
+    ///
+    /// # Safety
+    /// `data` must point to a live value of the type this lookup was collected from,
+    /// which must not be mutated for the duration of the call.
+    unsafe fn field(&self, data: NonNull<u8>) -> Option<NonNull<u8>> {
+        // SAFETY: `offset` is the offset of the enum within the value, and `locate` is
+        // the generated accessor for that enum.
+        unsafe { (self.locate)(data.add(self.offset)) }
+    }
+
+    /// # Safety
+    /// See [`field`](Self::field).
+    unsafe fn locate(&self, data: NonNull<u8>, type_id: TypeId) -> Option<NonNull<u8>> {
+        // SAFETY: Forwarded from the caller.
+        let field = unsafe { self.field(data)? };
+        if let Some(offsets) = self.offsets.get(&type_id) {
+            // SAFETY: The offset was computed from the metadata of the field's type.
+            return Some(unsafe { field.add(offsets[0]) });
+        }
+        self.variants
+            .iter()
+            // SAFETY: `field` points to a live value of the field's type.
+            .find_map(|variant| unsafe { variant.locate(field, type_id) })
+    }
+
+    /// # Safety
+    /// See [`field`](Self::field).
+    unsafe fn locate_all(&self, data: NonNull<u8>, type_id: TypeId, result: &mut Vec<NonNull<u8>>) {
+        // SAFETY: Forwarded from the caller.
+        let Some(field) = (unsafe { self.field(data) }) else {
+            return;
+        };
+        if let Some(offsets) = self.offsets.get(&type_id) {
+            // SAFETY: The offsets were computed from the metadata of the field's type.
+            result.extend(offsets.iter().map(|offset| unsafe { field.add(*offset) }));
+        }
+        for variant in &self.variants {
+            // SAFETY: `field` points to a live value of the field's type.
+            unsafe { variant.locate_all(field, type_id, result) };
+        }
+    }
+}
+
+type DestroyHook = Arc<dyn Fn(NonNull<u8>) + Send + Sync>;
+
+/// Destroy hooks registered at runtime for one concrete type.
+#[derive(Default)]
+pub(crate) struct DestroyHooks {
+    /// Whether `hooks` is non-empty, checked on every drop without locking.
+    registered: AtomicBool,
+    hooks: RwLock<Vec<DestroyHook>>,
+}
+
+impl DestroyHooks {
+    pub(crate) fn push(&self, hook: DestroyHook) {
+        let mut hooks = self.hooks.write().unwrap_or_else(PoisonError::into_inner);
+        hooks.push(hook);
+        self.registered.store(true, Ordering::Release);
+    }
+
+    /// Call every hook with the entity data.
+    ///
+    /// # Safety
+    /// `data` must point to a live value of the concrete type, not referenced anywhere
+    /// else for the duration of the call.
+    #[inline]
+    pub(crate) unsafe fn run(&self, data: NonNull<u8>) {
+        if !self.registered.load(Ordering::Acquire) {
+            return;
+        }
+        // Cloned so hooks can register further hooks.
+        let hooks = self
+            .hooks
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        for hook in hooks {
+            hook(data);
+        }
+    }
+}
+
+impl std::fmt::Debug for Extractor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Extractor")
+            .field("type_name", &self.type_name)
+            .field("size", &self.size)
+            .field("align", &self.align)
+            .field("components", &self.offsets.len())
+            .field("variants", &self.variants.len())
+            .finish()
     }
 }
@@ -0,0 +1,683 @@
+//! Serialization of entity graphs, preserving shared and weak references.
+//!
+//! Serializing a value through [`Graph`] (or [`serialize`]/[`deserialize`]) writes
+//! every entity reachable from it exactly once, however many `Acquirable`s point to
+//! it. Inside the graph, `Acquirable<T>` and `WeakAcquirable<T>` are written as entity
+//! IDs, and loading rebuilds the same sharing:
+//!
+//! - Every `Acquirable` to the same entity points to the same new entity.
+//! - A `WeakAcquirable` to an entity in the graph points to the new entity. A weak
+//!   reference to an entity that is not reachable through strong references (or
+//!   already dropped) loads as a reference that never upgrades.
+//!
+//! Entities are written as their concrete type, which has to be registered with
+//! `#[extractable(tag = "...")]` (see [`tagged`](crate::tagged)). Strong reference
+//! cycles cannot be rebuilt and fail to serialize. The format has three fields:
+//! `types` (the tag of each entity), `entities` (their values, with dependencies
+//! first) and `root`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//! use structecs::{graph::Graph, *};
+//!
+//! #[derive(Extractable, Serialize, Deserialize)]
+//! #[extractable(tag = "vehicle")]
+//! struct Vehicle {
+//!     passengers: Vec<Acquirable<Rider>>,
+//! }
+//!
+//! #[derive(Extractable, Serialize, Deserialize)]
+//! #[extractable(tag = "rider")]
+//! struct Rider {
+//!     name: String,
+//!     vehicle: WeakAcquirable<Vehicle>,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct World {
+//!     riders: Vec<Acquirable<Rider>>,
+//!     vehicles: Vec<Acquirable<Vehicle>>,
+//! }
+//!
+//! let alice = Acquirable::new(Rider {
+//!     name: "Alice".to_string(),
+//!     vehicle: Default::default(),
+//! });
+//! let world = World {
+//!     riders: vec![alice.clone()],
+//!     vehicles: vec![Acquirable::new(Vehicle { passengers: vec![alice] })],
+//! };
+//!
+//! let json = serde_json::to_string(&Graph(&world)).unwrap();
+//! let Graph(loaded): Graph<World> = serde_json::from_str(&json).unwrap();
+//!
+//! assert!(loaded.riders[0].ptr_eq(&loaded.vehicles[0].passengers[0]));
+//! ```
+
+use std::{
+    any::TypeId,
+    cell::RefCell,
+    fmt,
+    marker::PhantomData,
+    sync::{Arc, Weak},
+};
+
+use rustc_hash::FxHashMap;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{DeserializeSeed, Error as _, MapAccess, SeqAccess, Visitor},
+    ser::{Error as _, SerializeSeq, SerializeStruct},
+};
+
+use crate::{Acquirable, WeakAcquirable, entity::EntityData, tagged::SerdeType};
+
+/// Serializes and deserializes `T` as an entity graph.
+///
+/// # Example
+///
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use structecs::{graph::Graph, *};
+///
+/// #[derive(Extractable, Serialize, Deserialize)]
+/// #[extractable(tag = "item")]
+/// struct Item {
+///     id: u32,
+/// }
+///
+/// let item = Acquirable::new(Item { id: 1 });
+/// let pair = (item.clone(), item);
+///
+/// let json = serde_json::to_string(&Graph(&pair)).unwrap();
+/// let Graph((a, b)): Graph<(Acquirable<Item>, Acquirable<Item>)> =
+///     serde_json::from_str(&json).unwrap();
+/// assert!(a.ptr_eq(&b));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Graph<T>(pub T);
+
+impl<T: Serialize> Serialize for Graph<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Graph<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Graph)
+    }
+}
+
+const FIELDS: &[&str] = &["types", "entities", "root"];
+
+thread_local! {
+    static SERIALIZING: RefCell<Option<SerializeContext>> = const { RefCell::new(None) };
+    static DESERIALIZING: RefCell<Option<DeserializeContext>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone, Copy)]
+enum EntityState {
+    /// The entity's value is being discovered; a strong reference back to it is a cycle.
+    InProgress,
+    /// The entity has this ID.
+    Done(u64),
+}
+
+struct SerializeContext {
+    discovering: bool,
+    states: FxHashMap<*const EntityData, EntityState>,
+    /// Reachable entities, dependencies first. The index is the entity's ID.
+    entities: Vec<(Arc<EntityData>, &'static SerdeType)>,
+}
+
+struct DeserializeContext {
+    /// Entities by ID, allocated up front so weak references can point to them early.
+    entities: Vec<Arc<EntityData>>,
+}
+
+/// Clears the thread's context when serialization or deserialization ends.
+struct Scope<C: 'static>(&'static std::thread::LocalKey<RefCell<Option<C>>>);
+
+impl<C: 'static> Scope<C> {
+    fn enter(
+        key: &'static std::thread::LocalKey<RefCell<Option<C>>>,
+        context: C,
+    ) -> Result<Self, &'static str> {
+        key.with_borrow_mut(|current| {
+            if current.is_some() {
+                return Err("entity graphs cannot be nested");
+            }
+            *current = Some(context);
+            Ok(Scope(key))
+        })
+    }
+}
+
+impl<C: 'static> Drop for Scope<C> {
+    fn drop(&mut self) {
+        self.0.with_borrow_mut(|current| *current = None);
+    }
+}
+
+/// Serialize `value` as an entity graph.
+pub fn serialize<T: Serialize + ?Sized, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let context = SerializeContext {
+        discovering: true,
+        states: FxHashMap::default(),
+        entities: Vec::new(),
+    };
+    let _scope = Scope::enter(&SERIALIZING, context).map_err(S::Error::custom)?;
+
+    value.serialize(Discard).map_err(S::Error::custom)?;
+    let entities = SERIALIZING.with_borrow_mut(|context| match context {
+        Some(context) => {
+            context.discovering = false;
+            context.entities.clone()
+        }
+        None => Vec::new(),
+    });
+
+    let mut state = serializer.serialize_struct("Graph", FIELDS.len())?;
+    state.serialize_field(FIELDS[0], &Types(&entities))?;
+    state.serialize_field(FIELDS[1], &Values(&entities))?;
+    state.serialize_field(FIELDS[2], value)?;
+    state.end()
+}
+
+/// Deserialize an entity graph written by [`serialize`].
+pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    let context = DeserializeContext {
+        entities: Vec::new(),
+    };
+    let _scope = Scope::enter(&DESERIALIZING, context).map_err(D::Error::custom)?;
+    deserializer.deserialize_struct("Graph", FIELDS, GraphVisitor(PhantomData))
+}
+
+#[inline]
+pub(crate) fn is_serializing() -> bool {
+    SERIALIZING.with_borrow(Option::is_some)
+}
+
+#[inline]
+pub(crate) fn is_deserializing() -> bool {
+    DESERIALIZING.with_borrow(Option::is_some)
+}
+
+/// Serialize a strong reference to `inner` inside the current graph.
+pub(crate) fn serialize_strong<S: Serializer>(
+    inner: &Arc<EntityData>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let key = Arc::as_ptr(inner);
+    let (discovering, state) = SERIALIZING.with_borrow(|context| match context {
+        Some(context) => (context.discovering, context.states.get(&key).copied()),
+        None => (false, None),
+    });
+    match (discovering, state) {
+        (true, None) => {
+            discover(inner).map_err(S::Error::custom)?;
+            serializer.serialize_unit()
+        }
+        (true, Some(EntityState::InProgress)) => Err(S::Error::custom(format_args!(
+            "entity `{}` is part of a strong reference cycle, which cannot be deserialized",
+            inner.extractor.type_name
+        ))),
+        (true, Some(EntityState::Done(_))) => serializer.serialize_unit(),
+        (false, Some(EntityState::Done(id))) => serializer.serialize_u64(id),
+        (false, _) => Err(S::Error::custom(format_args!(
+            "entity `{}` was not reached while discovering the graph",
+            inner.extractor.type_name
+        ))),
+    }
+}
+
+/// Serialize a weak reference to `inner` inside the current graph.
+///
+/// Entities that are not reachable through strong references are written as `None`.
+pub(crate) fn serialize_weak<S: Serializer>(
+    inner: &Weak<EntityData>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    // The allocation outlives `inner`, so the address cannot be reused by another
+    // entity and comparing it is enough.
+    let key = Weak::as_ptr(inner);
+    let id = SERIALIZING.with_borrow(|context| match context {
+        Some(context) if !context.discovering => match context.states.get(&key) {
+            Some(EntityState::Done(id)) => Some(*id),
+            _ => None,
+        },
+        _ => None,
+    });
+    match id {
+        Some(id) => serializer.serialize_some(&id),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Add the entity and everything it strongly references to the graph.
+fn discover(inner: &Arc<EntityData>) -> Result<(), erased_serde::Error> {
+    let serde_type = crate::tagged::find_type(inner.extractor.type_id).ok_or_else(|| {
+        <erased_serde::Error as serde::ser::Error>::custom(format_args!(
+            "type `{}` has no serde tag; register it with #[extractable(tag = \"...\")]",
+            inner.extractor.type_name
+        ))
+    })?;
+    let key = Arc::as_ptr(inner);
+    with_serialize_context(|context| {
+        context.states.insert(key, EntityState::InProgress);
+    });
+
+    {
+        #[cfg(feature = "locking")]
+        let _guard = inner.lock.read_recursive();
+        // SAFETY: `serde_type` was registered for the concrete type of this entity.
+        let value = unsafe { (serde_type.serialize)(&inner.data) };
+        value.serialize(Discard)?;
+    }
+
+    with_serialize_context(|context| {
+        let id = context.entities.len() as u64;
+        context.states.insert(key, EntityState::Done(id));
+        context.entities.push((Arc::clone(inner), serde_type));
+    });
+    Ok(())
+}
+
+fn with_serialize_context(f: impl FnOnce(&mut SerializeContext)) {
+    SERIALIZING.with_borrow_mut(|context| {
+        if let Some(context) = context {
+            f(context);
+        }
+    });
+}
+
+struct Types<'a>(&'a [(Arc<EntityData>, &'static SerdeType)]);
+
+impl Serialize for Types<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|(_, serde_type)| serde_type.tag))
+    }
+}
+
+struct Values<'a>(&'a [(Arc<EntityData>, &'static SerdeType)]);
+
+impl Serialize for Values<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for (inner, serde_type) in self.0 {
+            #[cfg(feature = "locking")]
+            let _guard = inner.lock.read_recursive();
+            // SAFETY: `serde_type` was registered for the concrete type of this entity.
+            let value = unsafe { (serde_type.serialize)(&inner.data) };
+            seq.serialize_element(value)?;
+        }
+        seq.end()
+    }
+}
+
+/// Get the entity with `id` in the graph being deserialized.
+fn entity<E: serde::de::Error>(id: u64) -> Result<Arc<EntityData>, E> {
+    DESERIALIZING
+        .with_borrow(|context| {
+            context
+                .as_ref()
+                .and_then(|context| context.entities.get(usize::try_from(id).ok()?))
+                .cloned()
+        })
+        .ok_or_else(|| E::custom(format_args!("unknown entity ID {}", id)))
+}
+
+/// Deserialize a strong reference inside the current graph.
+pub(crate) fn deserialize_strong<'de, T: 'static, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Acquirable<T>, D::Error> {
+    let id = u64::deserialize(deserializer)?;
+    let inner = entity::<D::Error>(id)?;
+    if !inner.is_ready() {
+        return Err(D::Error::custom(format_args!(
+            "strong reference to entity {} before its definition",
+            id
+        )));
+    }
+    inner
+        .extract::<T>()
+        .ok_or_else(|| missing_component::<T, D::Error>(&inner))
+}
+
+/// Deserialize a weak reference inside the current graph.
+pub(crate) fn deserialize_weak<'de, T: 'static, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<WeakAcquirable<T>, D::Error> {
+    let Some(id) = Option::<u64>::deserialize(deserializer)? else {
+        return Ok(WeakAcquirable::default());
+    };
+    let inner = entity::<D::Error>(id)?;
+    if inner.is_ready() {
+        return inner
+            .extract::<T>()
+            .map(|acquirable| acquirable.downgrade())
+            .ok_or_else(|| missing_component::<T, D::Error>(&inner));
+    }
+    // The data is not written yet, so only components at a fixed offset can be found.
+    let offset = inner
+        .extractor
+        .offset_of(TypeId::of::<T>())
+        .ok_or_else(|| missing_component::<T, D::Error>(&inner))?;
+    // SAFETY: The offset of T within the entity's concrete type, inside its allocation.
+    let target = unsafe { inner.data.add(offset).cast::<T>() };
+    Ok(WeakAcquirable::new_raw(target, &inner))
+}
+
+fn missing_component<T, E: serde::de::Error>(inner: &EntityData) -> E {
+    E::custom(format_args!(
+        "entity `{}` does not contain `{}`",
+        inner.extractor.type_name,
+        std::any::type_name::<T>()
+    ))
+}
+
+struct GraphVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for GraphVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an entity graph")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let types: Vec<String> = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        allocate::<A::Error>(&types)?;
+        seq.next_element_seed(EntitiesSeed)?
+            .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        seq.next_element()?
+            .ok_or_else(|| A::Error::invalid_length(2, &self))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+        // Entities have to be allocated before they are referenced, so the fields
+        // must come in the order they are written.
+        for (index, field) in FIELDS.iter().enumerate() {
+            let key: String = map
+                .next_key()?
+                .ok_or_else(|| A::Error::missing_field(field))?;
+            if key != *field {
+                return Err(A::Error::custom(format_args!(
+                    "expected field `{}` at position {}, found `{}`",
+                    field, index, key
+                )));
+            }
+            match index {
+                0 => allocate::<A::Error>(&map.next_value::<Vec<String>>()?)?,
+                1 => map.next_value_seed(EntitiesSeed)?,
+                _ => return map.next_value(),
+            }
+        }
+        Err(A::Error::missing_field(FIELDS[2]))
+    }
+}
+
+/// Allocate an uninitialized entity for each tag.
+fn allocate<E: serde::de::Error>(types: &[String]) -> Result<(), E> {
+    let entities = types
+        .iter()
+        .map(|tag| {
+            let serde_type = crate::tagged::find_tag(tag)
+                .ok_or_else(|| E::custom(format_args!("unknown serde tag \"{}\"", tag)))?;
+            Ok(Arc::new(EntityData::new_uninit((serde_type.extractor)())))
+        })
+        .collect::<Result<Vec<_>, E>>()?;
+    DESERIALIZING.with_borrow_mut(|context| {
+        if let Some(context) = context {
+            context.entities = entities;
+        }
+    });
+    Ok(())
+}
+
+struct EntitiesSeed;
+
+impl<'de> DeserializeSeed<'de> for EntitiesSeed {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntitiesSeed {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of entities")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let expected = DESERIALIZING
+            .with_borrow(|context| context.as_ref().map_or(0, |context| context.entities.len()));
+        for id in 0..expected {
+            seq.next_element_seed(EntitySeed(id as u64))?
+                .ok_or_else(|| A::Error::invalid_length(id, &"one value per entity type"))?;
+        }
+        if seq.next_element::<serde::de::IgnoredAny>()?.is_some() {
+            return Err(A::Error::invalid_length(
+                expected + 1,
+                &"one value per entity type",
+            ));
+        }
+        Ok(())
+    }
+}
+
+struct EntitySeed(u64);
+
+impl<'de> DeserializeSeed<'de> for EntitySeed {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let inner = entity::<D::Error>(self.0)?;
+        let serde_type = crate::tagged::find_type(inner.extractor.type_id)
+            .ok_or_else(|| D::Error::custom("entity type is no longer registered"))?;
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+        // SAFETY: The entity was allocated for the concrete type of `serde_type`, and is
+        // initialized exactly once, since each ID is deserialized once.
+        unsafe { (serde_type.deserialize_into)(&mut erased, inner.data) }
+            .map_err(D::Error::custom)?;
+        // SAFETY: The data was just written.
+        unsafe { inner.set_ready() };
+        Ok(())
+    }
+}
+
+/// Writes references as IDs inside a [`Graph`]; cannot be serialized on its own.
+impl<T: 'static> Serialize for WeakAcquirable<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !is_serializing() {
+            return Err(S::Error::custom(
+                "WeakAcquirable can only be serialized inside structecs::graph",
+            ));
+        }
+        serialize_weak(self.inner(), serializer)
+    }
+}
+
+/// Reads references as IDs inside a [`Graph`]; cannot be deserialized on its own.
+impl<'de, T: 'static> Deserialize<'de> for WeakAcquirable<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !is_deserializing() {
+            return Err(D::Error::custom(
+                "WeakAcquirable can only be deserialized inside structecs::graph",
+            ));
+        }
+        deserialize_weak(deserializer)
+    }
+}
+
+/// Serializer that visits a value and discards the output, used to discover entities.
+struct Discard;
+
+type DiscardResult = Result<(), erased_serde::Error>;
+
+macro_rules! discard_primitives {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, _: $ty) -> DiscardResult {
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl Serializer for Discard {
+    type Ok = ();
+    type Error = erased_serde::Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    discard_primitives! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_none(self) -> DiscardResult {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> DiscardResult {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> DiscardResult {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> DiscardResult {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> DiscardResult {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        value: &T,
+    ) -> DiscardResult {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self, erased_serde::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self, erased_serde::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, erased_serde::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, erased_serde::Error> {
+        Ok(self)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self, erased_serde::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, erased_serde::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, erased_serde::Error> {
+        Ok(self)
+    }
+}
+
+macro_rules! discard_compound {
+    ($($trait:ident { $($method:ident($($arg:ty),*)),* }),* $(,)?) => {
+        $(
+            impl serde::ser::$trait for Discard {
+                type Ok = ();
+                type Error = erased_serde::Error;
+
+                $(
+                    fn $method<T: Serialize + ?Sized>(
+                        &mut self,
+                        $(_: $arg,)*
+                        value: &T,
+                    ) -> DiscardResult {
+                        value.serialize(Discard)
+                    }
+                )*
+
+                fn end(self) -> DiscardResult {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+discard_compound! {
+    SerializeSeq { serialize_element() },
+    SerializeTuple { serialize_element() },
+    SerializeTupleStruct { serialize_field() },
+    SerializeTupleVariant { serialize_field() },
+    SerializeMap { serialize_key(), serialize_value() },
+    SerializeStruct { serialize_field(&'static str) },
+    SerializeStructVariant { serialize_field(&'static str) },
+}
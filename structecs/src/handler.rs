@@ -1,4 +1,6 @@
-use std::sync::Arc;
+use std::{any::TypeId, sync::Arc};
+
+use rustc_hash::FxHashMap;
 
 #[cfg(debug_assertions)]
 use crate::ExtractionMetadata;
@@ -291,6 +293,146 @@ impl<Base: Extractable, Args, Return> std::fmt::Debug for ComponentHandler<Base,
     }
 }
 
+/// A dispatch table mapping concrete entity types to their [`ComponentHandler`]s.
+///
+/// This provides virtual-method style dispatch over collections of `Acquirable<Base>`:
+/// calling the registry runs the handler registered for the entity's concrete type,
+/// or for the nearest registered type it contains.
+///
+/// # Type Parameters
+///
+/// - `Base`: The base struct type used for queries (e.g., `Entity`)
+/// - `Args`: The argument tuple type for the handler functions (default: `()`)
+/// - `Return`: The return type of the handler functions (default: `()`)
+///
+/// # Example
+///
+/// ```
+/// use structecs::*;
+///
+/// #[derive(Extractable)]
+/// pub struct Entity {
+///     pub id: u32,
+/// }
+///
+/// #[derive(Extractable)]
+/// #[extractable(entity)]
+/// pub struct Player {
+///     pub entity: Entity,
+///     pub name: String,
+/// }
+///
+/// #[derive(Extractable)]
+/// #[extractable(entity)]
+/// pub struct Zombie {
+///     pub entity: Entity,
+/// }
+///
+/// let mut on_death = HandlerRegistry::<Entity, (), String>::new();
+/// on_death.register::<Player>(|player, ()| format!("{} died", player.name));
+/// on_death.register::<Entity>(|entity, ()| format!("entity {} died", entity.id));
+///
+/// let entities: Vec<Acquirable<Entity>> = vec![
+///     Acquirable::new(Player { entity: Entity { id: 1 }, name: "Steve".to_string() })
+///         .extract()
+///         .unwrap(),
+///     Acquirable::new(Zombie { entity: Entity { id: 2 } }).extract().unwrap(),
+/// ];
+///
+/// assert_eq!(on_death.call(&entities[0], ()).unwrap(), "Steve died");
+/// // No Zombie handler, so the Entity handler is used
+/// assert_eq!(on_death.call(&entities[1], ()).unwrap(), "entity 2 died");
+/// ```
+pub struct HandlerRegistry<Base: Extractable, Args = (), Return = ()> {
+    handlers: FxHashMap<TypeId, ComponentHandler<Base, Args, Return>>,
+}
+
+impl<Base: Extractable, Args, Return> Default for HandlerRegistry<Base, Args, Return> {
+    fn default() -> Self {
+        Self {
+            handlers: FxHashMap::default(),
+        }
+    }
+}
+
+impl<Base: Extractable, Args, Return> HandlerRegistry<Base, Args, Return> {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler for entities of type `Concrete`, replacing any previous one.
+    ///
+    /// `Concrete` may be a concrete entity type (e.g. `Player`) or an intermediate type
+    /// (e.g. `LivingEntity`) that acts as the fallback for every entity containing it.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if `Concrete` does not contain `Base`, like
+    /// [`ComponentHandler::for_type`].
+    pub fn register<Concrete: Extractable>(
+        &mut self,
+        func: impl Fn(&Acquirable<Concrete>, Args) -> Return + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.handlers.insert(
+            TypeId::of::<Concrete>(),
+            ComponentHandler::for_type::<Concrete>(func),
+        );
+        self
+    }
+
+    /// Remove the handler registered for `Concrete`.
+    pub fn unregister<Concrete: Extractable>(
+        &mut self,
+    ) -> Option<ComponentHandler<Base, Args, Return>> {
+        self.handlers.remove(&TypeId::of::<Concrete>())
+    }
+
+    /// Check whether a handler is registered for exactly `Concrete`.
+    pub fn contains<Concrete: Extractable>(&self) -> bool {
+        self.handlers.contains_key(&TypeId::of::<Concrete>())
+    }
+
+    /// Get the handler that would be called for `entity`.
+    ///
+    /// The handler for the entity's concrete type wins. Otherwise, the type hierarchy is
+    /// searched breadth-first, so the handler of the nearest contained type is returned;
+    /// types at the same depth are tried in field declaration order.
+    pub fn resolve<E: Extractable>(
+        &self,
+        entity: &Acquirable<E>,
+    ) -> Option<&ComponentHandler<Base, Args, Return>> {
+        entity
+            .inner
+            .extractor
+            .hierarchy()
+            .find_map(|type_id| self.handlers.get(&type_id))
+    }
+
+    /// Call the handler for `entity`'s concrete type or its nearest registered ancestor.
+    ///
+    /// Returns `None` when no handler matches.
+    pub fn call<E: Extractable>(&self, entity: &Acquirable<E>, args: Args) -> Option<Return> {
+        Some(self.resolve(entity)?.call(entity, args))
+    }
+
+    /// Get the number of registered handlers.
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Check whether no handlers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+impl<Base: Extractable, Args, Return> std::fmt::Debug for HandlerRegistry<Base, Args, Return> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.handlers.values()).finish()
+    }
+}
+
 /// Helper function to search for a target type in extraction metadata.
 #[cfg(debug_assertions)]
 fn search_metadata(list: &[ExtractionMetadata], target: std::any::TypeId) -> bool {
@@ -308,6 +450,8 @@ fn search_metadata(list: &[ExtractionMetadata], target: std::any::TypeId) -> boo
                     return true;
                 }
             }
+            // Only present while the enum holds the variant.
+            ExtractionMetadata::Variant { .. } => {}
         }
     }
     false
@@ -1,6 +1,9 @@
 #![doc = include_str!("../README.md")]
 
-use std::{any::TypeId, sync::LazyLock};
+use std::{
+    any::TypeId,
+    sync::{LazyLock, PoisonError, RwLock},
+};
 
 use rustc_hash::FxHashMap;
 // Re-export the derive macro
@@ -8,19 +11,49 @@ pub use structecs_macros::Extractable;
 
 // Module declarations
 mod acquirable;
+mod acquirable_dyn;
 #[cfg(feature = "archetype")]
 mod archetype;
+pub mod contains;
 mod entity;
 mod extractable;
 mod extractor;
+#[cfg(feature = "serde")]
+pub mod graph;
 mod handler;
+#[cfg(feature = "locking")]
+mod lock;
+#[cfg(feature = "archetype")]
+pub mod observer;
+#[cfg(feature = "archetype")]
+mod rcu;
+pub mod registry;
+#[cfg(feature = "archetype")]
+mod sharded;
+#[cfg(feature = "serde")]
+pub mod tagged;
 
 // Public exports
 pub use acquirable::{Acquirable, WeakAcquirable};
+pub use acquirable_dyn::AcquirableDyn;
 #[cfg(feature = "archetype")]
 pub use archetype::Archetype;
-pub use extractable::{Extractable, ExtractableType, ExtractionMetadata};
-pub use handler::ComponentHandler;
+pub use contains::Contains;
+#[cfg(feature = "entity-id")]
+pub use entity::EntityId;
+pub use extractable::{DynMetadata, Extractable, ExtractableType, ExtractionMetadata};
+pub use extractor::Extractor;
+pub use handler::{ComponentHandler, HandlerRegistry};
+#[cfg(feature = "locking")]
+pub use lock::{AcquirableReadGuard, AcquirableWriteGuard};
+#[cfg(feature = "archetype")]
+pub use observer::{ArchetypeEvent, SubscriptionId};
+#[cfg(feature = "archetype")]
+pub use rcu::{RcuArchetype, RcuReadGuard};
+#[cfg(feature = "archetype")]
+pub use sharded::{ShardedArchetype, ShardedReadGuard};
+#[cfg(feature = "serde")]
+pub use tagged::SerdeType;
 
 pub mod __private {
     // Re-export inventory submit for use in derive macros
@@ -63,9 +96,52 @@ pub static GLOBAL_EXTRACTOR_CACHE: LazyLock<FxHashMap<TypeId, extractor::Extract
             .collect()
     });
 
+/// Extractors for types without an inventory entry, built on first use and leaked so
+/// they live as long as the static cache.
+///
+/// This covers instantiations of generic structs, as well as types whose inventory
+/// registration was lost (hand-written impls, or entries stripped by the linker).
+static LAZY_EXTRACTOR_CACHE: LazyLock<RwLock<FxHashMap<TypeId, &'static extractor::Extractor>>> =
+    LazyLock::new(Default::default);
+
 pub(crate) fn get_extractor<E: extractable::Extractable>() -> &'static extractor::Extractor {
     let type_id = TypeId::of::<E>();
-    // SAFETY: The GLOBAL_EXTRACTOR_CACHE is populated at program start with all
-    // extractable types via inventory, so the unwrap_unchecked is safe here.
-    unsafe { GLOBAL_EXTRACTOR_CACHE.get(&type_id).unwrap_unchecked() }
+    if let Some(extractor) = GLOBAL_EXTRACTOR_CACHE.get(&type_id) {
+        extractor.check_duplicates();
+        return extractor;
+    }
+    #[cfg(debug_assertions)]
+    if E::REGISTERED {
+        panic!(
+            "Extractable type `{}` has no inventory registration. \
+             Derive `Extractable`, or submit `ExtractableType::new::<{0}>()` \
+             with `inventory::submit!` when implementing it by hand.",
+            std::any::type_name::<E>()
+        );
+    }
+    let extractor = get_lazy_extractor::<E>(type_id);
+    extractor.check_duplicates();
+    extractor
+}
+
+#[cold]
+fn get_lazy_extractor<E: extractable::Extractable>(
+    type_id: TypeId,
+) -> &'static extractor::Extractor {
+    let cache = LAZY_EXTRACTOR_CACHE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(extractor) = cache.get(&type_id) {
+        return extractor;
+    }
+    drop(cache);
+
+    let mut cache = LAZY_EXTRACTOR_CACHE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    cache.entry(type_id).or_insert_with(|| {
+        Box::leak(Box::new(extractor::Extractor::new_type(
+            &const { extractable::ExtractableType::new::<E>() },
+        )))
+    })
 }
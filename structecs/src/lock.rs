@@ -0,0 +1,308 @@
+//! Per-entity locking for interior mutability of extracted components.
+//!
+//! With the `locking` feature enabled, every entity carries a single `RwLock`
+//! shared by all `Acquirable`s pointing into it. Entities created with
+//! [`Acquirable::new_locked`] are accessed through [`Acquirable::read`] and
+//! [`Acquirable::write`] instead of `Deref`, so a component extracted from a larger
+//! entity locks the whole entity exactly once.
+//!
+//! Entities created with [`Acquirable::new`] keep working through `Deref`, so enabling
+//! the feature does not affect code that never locks. They can be read through the
+//! guards as well, but not written.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structecs::*;
+//!
+//! #[derive(Extractable)]
+//! struct Health {
+//!     current: u32,
+//! }
+//!
+//! #[derive(Extractable)]
+//! #[extractable(health)]
+//! struct Player {
+//!     name: String,
+//!     health: Health,
+//! }
+//!
+//! let player = Acquirable::new_locked(Player {
+//!     name: "Steve".to_string(),
+//!     health: Health { current: 20 },
+//! });
+//!
+//! player.extract::<Health>().unwrap().write().current -= 5;
+//! assert_eq!(player.read().health.current, 15);
+//! ```
+//!
+//! # Enum variants
+//!
+//! Writing to an enum may replace the variant a component was extracted from. The
+//! guards check that the component is still present after locking, and panic if it is
+//! not. For entities without enum variants this check is skipped. Extracting from an
+//! entity containing enum variants read-locks it, which deadlocks when the same
+//! thread already holds its write guard.
+
+use std::{
+    fmt::Debug,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use parking_lot::{RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{Acquirable, AcquirableDyn, Extractable, entity::EntityData};
+
+/// RAII guard granting shared access to a component while the entity is read-locked.
+pub struct AcquirableReadGuard<'a, T: ?Sized> {
+    target: NonNull<T>,
+    _guard: RwLockReadGuard<'a, ()>,
+}
+
+/// RAII guard granting exclusive access to a component while the entity is write-locked.
+pub struct AcquirableWriteGuard<'a, T: ?Sized> {
+    target: NonNull<T>,
+    _guard: RwLockWriteGuard<'a, ()>,
+}
+
+impl<T: Extractable> Acquirable<T> {
+    /// Create an entity whose components can be written through [`write`](Self::write).
+    ///
+    /// Components of the entity are only accessed through the guards: dereferencing an
+    /// `Acquirable` pointing into it panics.
+    pub fn new_locked(target: T) -> Self {
+        let mut data = EntityData::new(target, crate::get_extractor::<T>());
+        data.locked = true;
+        let data = std::sync::Arc::new(data);
+        Acquirable::new_raw(data.data.cast(), data)
+    }
+}
+
+impl<T: 'static> Acquirable<T> {
+    /// Lock the entity for shared access and borrow this component.
+    ///
+    /// Blocks the current thread while another thread holds the write lock.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component was stored in an enum variant that has since been
+    /// replaced.
+    #[inline(always)]
+    pub fn read(&self) -> AcquirableReadGuard<'_, T> {
+        let guard = AcquirableReadGuard {
+            target: self.target,
+            _guard: self.inner.lock.read(),
+        };
+        self.assert_present();
+        guard
+    }
+
+    /// Lock the entity for exclusive access and mutably borrow this component.
+    ///
+    /// Blocks the current thread while any other guard for the same entity is alive,
+    /// including guards obtained through a different extracted component.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entity was not created with [`new_locked`](Self::new_locked), or
+    /// if the component was stored in an enum variant that has since been replaced.
+    #[inline(always)]
+    pub fn write(&self) -> AcquirableWriteGuard<'_, T> {
+        self.inner.assert_locked();
+        let guard = AcquirableWriteGuard {
+            target: self.target,
+            _guard: self.inner.lock.write(),
+        };
+        self.assert_present();
+        guard
+    }
+
+    /// Attempt to lock the entity for shared access without blocking.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same condition as [`read`](Self::read).
+    #[inline(always)]
+    pub fn try_read(&self) -> Option<AcquirableReadGuard<'_, T>> {
+        let guard = AcquirableReadGuard {
+            target: self.target,
+            _guard: self.inner.lock.try_read()?,
+        };
+        self.assert_present();
+        Some(guard)
+    }
+
+    /// Attempt to lock the entity for exclusive access without blocking.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same condition as [`write`](Self::write).
+    #[inline(always)]
+    pub fn try_write(&self) -> Option<AcquirableWriteGuard<'_, T>> {
+        self.inner.assert_locked();
+        let guard = AcquirableWriteGuard {
+            target: self.target,
+            _guard: self.inner.lock.try_write()?,
+        };
+        self.assert_present();
+        Some(guard)
+    }
+
+    /// Like [`read`](Self::read), but does not block while the current thread already
+    /// holds a read guard on the entity and another thread waits to write.
+    #[cfg(feature = "serde")]
+    #[inline(always)]
+    pub(crate) fn read_recursive(&self) -> AcquirableReadGuard<'_, T> {
+        let guard = AcquirableReadGuard {
+            target: self.target,
+            _guard: self.inner.lock.read_recursive(),
+        };
+        self.assert_present();
+        guard
+    }
+
+    /// Check that the component is still present. Must be called while locked.
+    #[inline(always)]
+    fn assert_present(&self) {
+        // SAFETY: The caller holds the entity lock, so the data cannot change meanwhile.
+        if !unsafe {
+            self.inner
+                .extractor
+                .is_present(self.inner.data, self.target)
+        } {
+            panic!(
+                "Component `{}` is no longer present in entity `{}`: the enum variant \
+                 holding it was replaced",
+                std::any::type_name::<T>(),
+                self.inner.extractor.type_name
+            );
+        }
+    }
+}
+
+impl<D: ?Sized + 'static> AcquirableDyn<D> {
+    /// Lock the entity for shared access and borrow it as the trait object.
+    ///
+    /// Blocks the current thread while another thread holds the write lock.
+    #[inline(always)]
+    pub fn read(&self) -> AcquirableReadGuard<'_, D> {
+        AcquirableReadGuard {
+            target: self.target,
+            _guard: self.inner.lock.read(),
+        }
+    }
+
+    /// Lock the entity for exclusive access and mutably borrow it as the trait object.
+    ///
+    /// Blocks the current thread while any other guard for the same entity is alive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entity was not created with [`Acquirable::new_locked`].
+    #[inline(always)]
+    pub fn write(&self) -> AcquirableWriteGuard<'_, D> {
+        self.inner.assert_locked();
+        AcquirableWriteGuard {
+            target: self.target,
+            _guard: self.inner.lock.write(),
+        }
+    }
+
+    /// Attempt to lock the entity for shared access without blocking.
+    #[inline(always)]
+    pub fn try_read(&self) -> Option<AcquirableReadGuard<'_, D>> {
+        Some(AcquirableReadGuard {
+            target: self.target,
+            _guard: self.inner.lock.try_read()?,
+        })
+    }
+
+    /// Attempt to lock the entity for exclusive access without blocking.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same condition as [`write`](Self::write).
+    #[inline(always)]
+    pub fn try_write(&self) -> Option<AcquirableWriteGuard<'_, D>> {
+        self.inner.assert_locked();
+        Some(AcquirableWriteGuard {
+            target: self.target,
+            _guard: self.inner.lock.try_write()?,
+        })
+    }
+}
+
+impl EntityData {
+    /// Panic unless the entity was created with `Acquirable::new_locked`.
+    #[inline(always)]
+    fn assert_locked(&self) {
+        if !self.locked {
+            panic!(
+                "Entity `{}` cannot be written: create it with `Acquirable::new_locked`, \
+                 or `insert_locked` when inserting it into an archetype",
+                self.extractor.type_name
+            );
+        }
+    }
+
+    /// Panic if the entity was created with `Acquirable::new_locked`.
+    #[inline(always)]
+    pub(crate) fn assert_unlocked(&self) {
+        if self.locked {
+            panic!(
+                "Entity `{}` was created with `Acquirable::new_locked`: access it through \
+                 `read()` or `write()`",
+                self.extractor.type_name
+            );
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for AcquirableReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The entity is read-locked for the lifetime of the guard, so no
+        // `&mut T` into the same entity can exist concurrently.
+        unsafe { self.target.as_ref() }
+    }
+}
+
+impl<T: ?Sized> Deref for AcquirableWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The entity is write-locked for the lifetime of the guard.
+        unsafe { self.target.as_ref() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for AcquirableWriteGuard<'_, T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: The entity is write-locked for the lifetime of the guard, so this
+        // is the only reference into the entity data.
+        unsafe { self.target.as_mut() }
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for AcquirableReadGuard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for AcquirableWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+// SAFETY: The guards only hand out references to `T`, and sharing the read guard
+// across threads is equivalent to sharing `&T`.
+unsafe impl<T: ?Sized + Sync> Sync for AcquirableReadGuard<'_, T> {}
+// SAFETY: Sharing the write guard across threads only exposes `&T` through `Deref`.
+unsafe impl<T: ?Sized + Sync> Sync for AcquirableWriteGuard<'_, T> {}
@@ -0,0 +1,218 @@
+//! Notifications of entities entering and leaving an [`Archetype`](crate::Archetype).
+//!
+//! Observers registered with [`Archetype::subscribe`](crate::Archetype::subscribe) are
+//! called with an [`ArchetypeEvent`] for every insertion, overwrite and removal.
+//!
+//! Events are queued while the archetype is locked, so they are delivered in the order
+//! the modifications happened, and observers are called after the lock is released, so
+//! they can freely access the archetype. When several threads modify the archetype at
+//! once, one of them delivers the events of all of them; an event is therefore not
+//! necessarily delivered by the time the method that caused it returns. Events caused
+//! by an observer are delivered after the observers of the current event returned.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+};
+
+use parking_lot::{Mutex, RwLock};
+
+use crate::Acquirable;
+
+/// A change to the entries of an [`Archetype`](crate::Archetype).
+#[derive(Debug, Clone)]
+pub enum ArchetypeEvent<Key, Base: 'static> {
+    /// An entity was inserted under a new key.
+    Inserted { key: Key, value: Acquirable<Base> },
+    /// An entity was inserted under a key that was already present.
+    Replaced {
+        key: Key,
+        old: Acquirable<Base>,
+        new: Acquirable<Base>,
+    },
+    /// An entity was removed, including by `clear`, `retain` and `drain_filter`.
+    Removed { key: Key, value: Acquirable<Base> },
+}
+
+impl<Key: Copy, Base: 'static> ArchetypeEvent<Key, Base> {
+    /// The key of the changed entry.
+    pub fn key(&self) -> Key {
+        match self {
+            Self::Inserted { key, .. } | Self::Replaced { key, .. } | Self::Removed { key, .. } => {
+                *key
+            }
+        }
+    }
+
+    /// The event as seen by an observer of the entities containing `U`.
+    ///
+    /// Returns `None` if neither entity involved contains `U`. Replacing an entity that
+    /// does not contain `U` with one that does is seen as an insertion, and the reverse
+    /// as a removal.
+    pub fn extract<U: 'static>(&self) -> Option<ArchetypeEvent<Key, U>> {
+        match self {
+            Self::Inserted { key, value } => Some(ArchetypeEvent::Inserted {
+                key: *key,
+                value: value.extract()?,
+            }),
+            Self::Replaced { key, old, new } => match (old.extract(), new.extract()) {
+                (Some(old), Some(new)) => Some(ArchetypeEvent::Replaced {
+                    key: *key,
+                    old,
+                    new,
+                }),
+                (None, Some(value)) => Some(ArchetypeEvent::Inserted { key: *key, value }),
+                (Some(value), None) => Some(ArchetypeEvent::Removed { key: *key, value }),
+                (None, None) => None,
+            },
+            Self::Removed { key, value } => Some(ArchetypeEvent::Removed {
+                key: *key,
+                value: value.extract()?,
+            }),
+        }
+    }
+}
+
+/// Identifies an observer registered with [`Archetype::subscribe`](crate::Archetype::subscribe).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Observer<Key, Base> = Arc<dyn Fn(&ArchetypeEvent<Key, Base>) + Send + Sync>;
+
+/// The observers of an archetype, and the events waiting to be delivered to them.
+pub(crate) struct Observers<Key, Base: 'static> {
+    observers: RwLock<Vec<(SubscriptionId, Observer<Key, Base>)>>,
+    /// The length of `observers`, checked without locking before creating events.
+    count: AtomicUsize,
+    next_id: AtomicU64,
+    queue: Mutex<EventQueue<Key, Base>>,
+}
+
+struct EventQueue<Key, Base: 'static> {
+    events: VecDeque<ArchetypeEvent<Key, Base>>,
+    /// Set while a thread is delivering the queued events.
+    dispatching: bool,
+}
+
+impl<Key, Base: 'static> Default for Observers<Key, Base> {
+    fn default() -> Self {
+        Self {
+            observers: RwLock::new(Vec::new()),
+            count: AtomicUsize::new(0),
+            next_id: AtomicU64::new(0),
+            queue: Mutex::new(EventQueue {
+                events: VecDeque::new(),
+                dispatching: false,
+            }),
+        }
+    }
+}
+
+impl<Key, Base: 'static> fmt::Debug for Observers<Key, Base> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Observers")
+            .field("count", &self.count.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Key, Base: 'static> Observers<Key, Base> {
+    pub(crate) fn subscribe(
+        &self,
+        observer: impl Fn(&ArchetypeEvent<Key, Base>) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut observers = self.observers.write();
+        observers.push((id, Arc::new(observer)));
+        self.count.store(observers.len(), Ordering::Relaxed);
+        id
+    }
+
+    pub(crate) fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut observers = self.observers.write();
+        let len = observers.len();
+        observers.retain(|(observer, _)| *observer != id);
+        self.count.store(observers.len(), Ordering::Relaxed);
+        observers.len() != len
+    }
+
+    /// Check whether events should be created at all.
+    #[inline]
+    pub(crate) fn is_observed(&self) -> bool {
+        self.count.load(Ordering::Relaxed) != 0
+    }
+
+    /// Queue `events` while `guard` is held, then release it and deliver them.
+    pub(crate) fn notify<G>(
+        &self,
+        guard: G,
+        events: impl IntoIterator<Item = ArchetypeEvent<Key, Base>>,
+    ) {
+        let queued = {
+            let mut queue = self.queue.lock();
+            let len = queue.events.len();
+            queue.events.extend(events);
+            queue.events.len() != len
+        };
+        drop(guard);
+        if queued {
+            self.dispatch();
+        }
+    }
+
+    /// Deliver the queued events, unless another thread already is.
+    fn dispatch(&self) {
+        {
+            let mut queue = self.queue.lock();
+            if queue.dispatching {
+                return;
+            }
+            queue.dispatching = true;
+        }
+        let mut dispatching = Dispatching {
+            queue: &self.queue,
+            done: false,
+        };
+
+        loop {
+            let event = {
+                let mut queue = self.queue.lock();
+                let Some(event) = queue.events.pop_front() else {
+                    // Cleared under the same lock as the check, so events queued from
+                    // now on are delivered by the thread that queued them.
+                    queue.dispatching = false;
+                    dispatching.done = true;
+                    return;
+                };
+                event
+            };
+            let observers: Vec<_> = self
+                .observers
+                .read()
+                .iter()
+                .map(|(_, observer)| Arc::clone(observer))
+                .collect();
+            for observer in observers {
+                observer(&event);
+            }
+        }
+    }
+}
+
+/// Hands delivery over to the next thread queueing an event if an observer panics.
+struct Dispatching<'a, Key, Base: 'static> {
+    queue: &'a Mutex<EventQueue<Key, Base>>,
+    done: bool,
+}
+
+impl<Key, Base: 'static> Drop for Dispatching<'_, Key, Base> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.queue.lock().dispatching = false;
+        }
+    }
+}
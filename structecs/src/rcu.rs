@@ -0,0 +1,367 @@
+//! An [`Archetype`](crate::Archetype) whose reads never wait for writers.
+//!
+//! `RcuArchetype` keeps its entries in an immutable snapshot. Readers access the
+//! current snapshot directly, without taking any lock, so `get` never blocks behind an
+//! `insert` or `remove`. Writers copy the snapshot, modify the copy, publish it, and
+//! wait until no reader can still be using the old snapshot before freeing it
+//! (read-copy-update).
+//!
+//! Every write copies the whole collection, so this suits collections that are read
+//! far more often than they are modified. Several modifications can share one copy
+//! with [`update`](RcuArchetype::update).
+//!
+//! # Example
+//!
+//! ```rust
+//! use structecs::*;
+//!
+//! #[derive(Extractable)]
+//! struct Entity {
+//!     id: u32,
+//! }
+//!
+//! let entities: RcuArchetype<u32, Entity> = RcuArchetype::default();
+//! entities.insert(1, Entity { id: 1 });
+//!
+//! // Many threads can call `get` without ever waiting for a writer
+//! assert_eq!(entities.get(&1).unwrap().id, 1);
+//!
+//! // Insert a batch of entities with a single copy
+//! entities.update(|map| {
+//!     for id in 2..10 {
+//!         map.insert(id, Acquirable::new(Entity { id }));
+//!     }
+//! });
+//! assert_eq!(entities.len(), 9);
+//! ```
+//!
+//! # Writers and readers
+//!
+//! A write waits for every read that started before it was published, including
+//! callbacks passed to [`for_each`](RcuArchetype::for_each) and guards returned by
+//! [`read`](RcuArchetype::read). Writing to an archetype from inside one of its own
+//! reads therefore never returns.
+
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    ops::Deref,
+    sync::{
+        Arc,
+        atomic::{AtomicPtr, AtomicUsize, Ordering},
+    },
+};
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+#[cfg(feature = "locking")]
+use crate::archetype::new_locked_entity;
+use crate::{
+    Acquirable, Extractable,
+    archetype::{Entries, new_entity},
+};
+
+/// A thread-safe collection like [`Archetype`](crate::Archetype), with wait-free reads.
+pub struct RcuArchetype<Key: Copy + Eq + Hash, Base: Extractable> {
+    inner: Arc<Snapshots<Key, Base>>,
+}
+
+/// The current snapshot, and the readers that may still be using earlier ones.
+struct Snapshots<Key, Base: 'static> {
+    current: AtomicPtr<Entries<Key, Base>>,
+    /// Selects which of `readers` new readers register with. Only changed by writers.
+    epoch: AtomicUsize,
+    readers: [ReaderCount; 2],
+    /// Serializes writers, so each one copies the snapshot published by the last.
+    writer: Mutex<()>,
+}
+
+/// Kept on its own cache line, since every read modifies it.
+#[repr(align(128))]
+struct ReaderCount(AtomicUsize);
+
+// SAFETY: Snapshots owns the `Entries` behind `current`. They are shared with readers on
+// other threads, and dropped by whichever thread replaces them.
+unsafe impl<Key, Base: 'static> Send for Snapshots<Key, Base> where Entries<Key, Base>: Send + Sync {}
+// SAFETY: See above.
+unsafe impl<Key, Base: 'static> Sync for Snapshots<Key, Base> where Entries<Key, Base>: Send + Sync {}
+
+impl<Key, Base: 'static> Drop for Snapshots<Key, Base> {
+    fn drop(&mut self) {
+        // SAFETY: `current` was created by `Box::into_raw`, and there are no readers
+        // left once the last `RcuArchetype` is dropped.
+        drop(unsafe { Box::from_raw(*self.current.get_mut()) });
+    }
+}
+
+impl<Key: Copy + Eq + Hash, Base: 'static> Snapshots<Key, Base> {
+    fn new(entries: Entries<Key, Base>) -> Self {
+        Self {
+            current: AtomicPtr::new(Box::into_raw(Box::new(entries))),
+            epoch: AtomicUsize::new(0),
+            readers: [
+                ReaderCount(AtomicUsize::new(0)),
+                ReaderCount(AtomicUsize::new(0)),
+            ],
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Register a reader, keeping the snapshot it loads alive until it is dropped.
+    fn register(&self) -> Reader<'_, Key, Base> {
+        let parity = self.epoch.load(Ordering::SeqCst) & 1;
+        self.readers[parity].0.fetch_add(1, Ordering::SeqCst);
+        // SAFETY: Writers free a snapshot only after every reader registered before it
+        // was replaced has been dropped, and this reader is registered.
+        let entries = unsafe { &*self.current.load(Ordering::SeqCst) };
+        Reader {
+            snapshots: self,
+            parity,
+            entries,
+        }
+    }
+
+    /// Publish a modified copy of the current snapshot.
+    fn publish<R>(&self, f: impl FnOnce(&mut Entries<Key, Base>) -> R) -> R {
+        let writer = self.writer.lock();
+        let previous = self.current.load(Ordering::SeqCst);
+        // SAFETY: Only writers replace `current`, and this writer holds the lock.
+        let mut next = Box::new(unsafe { &*previous }.clone());
+        let result = f(&mut next);
+        next.reindex();
+        self.current.store(Box::into_raw(next), Ordering::SeqCst);
+        self.synchronize();
+        drop(writer);
+
+        // SAFETY: `previous` was created by `Box::into_raw`, and after `synchronize` no
+        // reader can still be using it. It is dropped after releasing the writer lock,
+        // in case dropping an entity modifies this archetype.
+        drop(unsafe { Box::from_raw(previous) });
+        result
+    }
+
+    /// Wait until every reader registered before this call has been dropped.
+    ///
+    /// New readers register with the other counter after each flip, so each counter
+    /// drains even while reads keep arriving.
+    fn synchronize(&self) {
+        for _ in 0..2 {
+            let parity = self.epoch.fetch_add(1, Ordering::SeqCst) & 1;
+            while self.readers[parity].0.load(Ordering::SeqCst) != 0 {
+                std::thread::yield_now();
+            }
+        }
+    }
+}
+
+/// A registered reader of a snapshot.
+struct Reader<'a, Key, Base: 'static> {
+    snapshots: &'a Snapshots<Key, Base>,
+    parity: usize,
+    entries: &'a Entries<Key, Base>,
+}
+
+impl<Key, Base: 'static> Drop for Reader<'_, Key, Base> {
+    fn drop(&mut self) {
+        self.snapshots.readers[self.parity]
+            .0
+            .fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<Key: Copy + Eq + Hash, Base: Extractable> Default for RcuArchetype<Key, Base> {
+    fn default() -> Self {
+        Self::from_map(FxHashMap::default())
+    }
+}
+
+impl<Key: Copy + Eq + Hash, Base: Extractable> Clone for RcuArchetype<Key, Base> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<Key: Copy + Eq + Hash + Debug, Base: Extractable + Debug> Debug for RcuArchetype<Key, Base> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RcuArchetype")
+            .field("map", &*self.read())
+            .finish()
+    }
+}
+
+impl<Key: Copy + Eq + Hash, Base: Extractable> RcuArchetype<Key, Base> {
+    fn from_map(map: FxHashMap<Key, Acquirable<Base>>) -> Self {
+        Self {
+            inner: Arc::new(Snapshots::new(Entries::new(map))),
+        }
+    }
+
+    /// Insert an entity, copying the collection.
+    pub fn insert<U: Extractable>(&self, key: Key, value: U) -> Acquirable<U> {
+        let (acquirable, insert) = new_entity::<U, Base>(value);
+        drop(self.inner.publish(|entries| entries.insert(key, insert)));
+        acquirable
+    }
+
+    /// Like [`insert`](Self::insert), but create the entity with
+    /// [`Acquirable::new_locked`], so its components can be modified through
+    /// [`Acquirable::write`].
+    #[cfg(feature = "locking")]
+    pub fn insert_locked<U: Extractable>(&self, key: Key, value: U) -> Acquirable<U> {
+        let (acquirable, insert) = new_locked_entity::<U, Base>(value);
+        drop(self.inner.publish(|entries| entries.insert(key, insert)));
+        acquirable
+    }
+
+    pub fn get(&self, key: &Key) -> Option<Acquirable<Base>> {
+        self.inner.register().entries.map.get(key).cloned()
+    }
+
+    /// Remove an entity, copying the collection if it is present.
+    pub fn remove(&self, key: &Key) -> Option<Acquirable<Base>> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        self.inner.publish(|entries| entries.remove(key))
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.inner.register().entries.map.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.register().entries.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.register().entries.map.is_empty()
+    }
+
+    pub fn clear(&self) {
+        let snapshots = &self.inner;
+        let writer = snapshots.writer.lock();
+        let previous = snapshots.current.swap(
+            Box::into_raw(Box::new(Entries::new(FxHashMap::default()))),
+            Ordering::SeqCst,
+        );
+        snapshots.synchronize();
+        drop(writer);
+        // SAFETY: As in `Snapshots::publish`.
+        drop(unsafe { Box::from_raw(previous) });
+    }
+
+    /// Modify a copy of the entries, and publish it in a single step.
+    ///
+    /// Readers see either none or all of the modifications made by `f`.
+    pub fn update<R>(&self, f: impl FnOnce(&mut FxHashMap<Key, Acquirable<Base>>) -> R) -> R {
+        self.inner.publish(|entries| {
+            entries.invalidate();
+            f(&mut entries.map)
+        })
+    }
+
+    /// Collect the entries whose entity contains `U`, extracted as `U`.
+    ///
+    /// See [`Archetype::iter_as`](crate::Archetype::iter_as). The entries come from a
+    /// single snapshot.
+    pub fn iter_as<U: 'static>(&self) -> std::vec::IntoIter<(Key, Acquirable<U>)> {
+        let mut out = Vec::new();
+        self.inner.register().entries.collect_as(&mut out);
+        out.into_iter()
+    }
+
+    /// Call `f` on every entry of the current snapshot.
+    ///
+    /// Writers wait for `f` to return, so it must not modify this archetype.
+    pub fn for_each(&self, mut f: impl FnMut(&Key, &Acquirable<Base>)) {
+        for (key, value) in self.read().iter() {
+            f(key, value);
+        }
+    }
+
+    /// Collect the entries of the current snapshot for which `predicate` returns `true`.
+    ///
+    /// Writers wait for `predicate` to return, so it must not modify this archetype.
+    pub fn filter(
+        &self,
+        mut predicate: impl FnMut(&Key, &Acquirable<Base>) -> bool,
+    ) -> Vec<(Key, Acquirable<Base>)> {
+        self.read()
+            .iter()
+            .filter(|(key, value)| predicate(key, value))
+            .map(|(key, value)| (*key, value.clone()))
+            .collect()
+    }
+
+    /// Remove the entries for which `predicate` returns `false`.
+    ///
+    /// `predicate` runs while holding the writer lock, so it must not modify this
+    /// archetype. Removed entities are dropped after the lock is released.
+    pub fn retain(&self, mut predicate: impl FnMut(&Key, &Acquirable<Base>) -> bool) {
+        drop(self.drain_filter(|key, value| !predicate(key, value)));
+    }
+
+    /// Remove and return the entries for which `predicate` returns `true`.
+    ///
+    /// `predicate` runs while holding the writer lock, so it must not modify this
+    /// archetype.
+    pub fn drain_filter(
+        &self,
+        mut predicate: impl FnMut(&Key, &Acquirable<Base>) -> bool,
+    ) -> Vec<(Key, Acquirable<Base>)> {
+        self.inner.publish(|entries| {
+            let mut drained = Vec::new();
+            entries.drain_filter(&mut predicate, &mut drained);
+            drained
+        })
+    }
+
+    /// Access the current snapshot.
+    ///
+    /// Reads never wait, but writers wait for the guard to be dropped before freeing
+    /// the snapshot it refers to, so it should not be held for long.
+    pub fn read(&self) -> RcuReadGuard<'_, Key, Base> {
+        RcuReadGuard {
+            reader: self.inner.register(),
+        }
+    }
+}
+
+/// Guard over a snapshot of an [`RcuArchetype`].
+pub struct RcuReadGuard<'a, Key: Copy + Eq + Hash, Base: Extractable> {
+    reader: Reader<'a, Key, Base>,
+}
+
+impl<Key: Copy + Eq + Hash, Base: Extractable> Deref for RcuReadGuard<'_, Key, Base> {
+    type Target = FxHashMap<Key, Acquirable<Base>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.reader.entries.map
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<Key, Base> serde::Serialize for RcuArchetype<Key, Base>
+where
+    Key: Copy + Eq + Hash + serde::Serialize,
+    Base: Extractable,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let map = self.read();
+        crate::archetype::serialize_entries(serializer, map.len(), map.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, Key, Base> serde::Deserialize<'de> for RcuArchetype<Key, Base>
+where
+    Key: Copy + Eq + Hash + serde::Deserialize<'de>,
+    Base: Extractable,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::archetype::deserialize_entries(deserializer).map(Self::from_map)
+    }
+}
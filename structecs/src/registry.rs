@@ -0,0 +1,126 @@
+//! Runtime introspection of extractable types.
+//!
+//! Every type deriving `Extractable` is registered at program start; instantiations of
+//! generic types are registered the first time an `Acquirable` is created for them.
+//! The functions in this module expose those registrations as [`Extractor`]s, which
+//! describe a concrete type's name, layout, and the components extractable from it.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::any::TypeId;
+//! use structecs::*;
+//!
+//! #[derive(Extractable)]
+//! struct LivingEntity {
+//!     health: u32,
+//! }
+//!
+//! #[derive(Extractable)]
+//! #[extractable(living)]
+//! struct Player {
+//!     name: String,
+//!     living: LivingEntity,
+//! }
+//!
+//! // What can be extracted from a Player?
+//! let player = registry::find_type(TypeId::of::<Player>()).unwrap();
+//! assert!(player.contains(TypeId::of::<LivingEntity>()));
+//!
+//! // Which types contain a LivingEntity?
+//! let kinds = registry::types_containing(TypeId::of::<LivingEntity>());
+//! assert!(kinds.iter().any(|kind| kind.type_id() == TypeId::of::<Player>()));
+//! ```
+
+use std::{
+    any::TypeId,
+    sync::{Arc, PoisonError},
+};
+
+use crate::{Extractable, GLOBAL_EXTRACTOR_CACHE, LAZY_EXTRACTOR_CACHE, extractor::Extractor};
+
+/// List every extractable type registered so far.
+///
+/// Generic instantiations only appear once they have been used. Types rejected for
+/// containing duplicated components are left out.
+pub fn registered_types() -> Vec<&'static Extractor> {
+    let lazy = LAZY_EXTRACTOR_CACHE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    GLOBAL_EXTRACTOR_CACHE
+        .values()
+        .chain(lazy.values().copied())
+        .filter(|extractor| extractor.duplicate_error.is_none())
+        .collect()
+}
+
+/// Look up the registration of the type identified by `type_id`.
+pub fn find_type(type_id: TypeId) -> Option<&'static Extractor> {
+    if let Some(extractor) = GLOBAL_EXTRACTOR_CACHE.get(&type_id) {
+        return Some(extractor).filter(|extractor| extractor.duplicate_error.is_none());
+    }
+    LAZY_EXTRACTOR_CACHE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&type_id)
+        .copied()
+        .filter(|extractor| extractor.duplicate_error.is_none())
+}
+
+/// List every registered type that `type_id` can be extracted from.
+///
+/// The type itself is included when it is registered, since every type can be
+/// extracted from itself.
+pub fn types_containing(type_id: TypeId) -> Vec<&'static Extractor> {
+    registered_types()
+        .into_iter()
+        .filter(|extractor| extractor.contains(type_id))
+        .collect()
+}
+
+/// List every registered type that can be extracted as the trait object type
+/// `type_id`, such as `TypeId::of::<dyn Trait>()`.
+pub fn types_implementing(type_id: TypeId) -> Vec<&'static Extractor> {
+    registered_types()
+        .into_iter()
+        .filter(|extractor| extractor.implements(type_id))
+        .collect()
+}
+
+/// Register a hook called with every entity of the concrete type `T` when it is
+/// destroyed.
+///
+/// Hooks run when the last strong reference to an entity is dropped, on the thread
+/// dropping it. They run after the hooks registered on the entity itself with
+/// [`Acquirable::on_destroy`](crate::Acquirable::on_destroy), and before the hook set
+/// with `#[extractable(on_destroy = ...)]`. Entities containing `T` as a component,
+/// but of another concrete type, do not call it.
+///
+/// # Example
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use structecs::*;
+///
+/// #[derive(Extractable)]
+/// struct Projectile {
+///     damage: u32,
+/// }
+///
+/// static DESPAWNED: AtomicUsize = AtomicUsize::new(0);
+/// registry::on_destroy::<Projectile>(|_| {
+///     DESPAWNED.fetch_add(1, Ordering::SeqCst);
+/// });
+///
+/// drop(Acquirable::new(Projectile { damage: 3 }));
+/// assert_eq!(DESPAWNED.load(Ordering::SeqCst), 1);
+/// ```
+pub fn on_destroy<T: Extractable>(hook: impl Fn(&mut T) + Send + Sync + 'static) {
+    crate::get_extractor::<T>()
+        .destroy_hooks
+        .push(Arc::new(move |data| {
+            // SAFETY: Hooks of this extractor are only called with the data of a `T`
+            // being destroyed, which nothing else references.
+            hook(unsafe { data.cast::<T>().as_mut() })
+        }));
+}
@@ -0,0 +1,319 @@
+//! An [`Archetype`](crate::Archetype) split into independently locked shards.
+//!
+//! `Archetype` guards all of its entries with a single `RwLock`, so threads inserting
+//! or removing entities contend on it even when they touch unrelated keys.
+//! `ShardedArchetype` hashes each key to one of several shards, each with its own lock,
+//! so operations on a single key only contend with operations on the same shard.
+//!
+//! Operations over the whole collection (`len`, `iter_as`, `for_each`, `read`, ...)
+//! lock every shard, always in the same order, and therefore see a consistent snapshot.
+//!
+//! # Example
+//!
+//! ```rust
+//! use structecs::*;
+//!
+//! #[derive(Extractable)]
+//! struct Entity {
+//!     id: u32,
+//! }
+//!
+//! #[derive(Extractable)]
+//! #[extractable(entity)]
+//! struct Player {
+//!     name: String,
+//!     entity: Entity,
+//! }
+//!
+//! let entities: ShardedArchetype<u32, Entity> = ShardedArchetype::with_shards(8);
+//!
+//! entities.insert(
+//!     1,
+//!     Player {
+//!         name: "Alice".to_string(),
+//!         entity: Entity { id: 1 },
+//!     },
+//! );
+//! entities.insert(2, Entity { id: 2 });
+//!
+//! assert_eq!(entities.len(), 2);
+//! assert_eq!(entities.get(&2).unwrap().id, 2);
+//!
+//! let players: Vec<_> = entities.iter_as::<Player>().collect();
+//! assert_eq!(players.len(), 1);
+//! assert_eq!(players[0].1.name, "Alice");
+//! ```
+
+use std::{
+    hash::{BuildHasher, Hash},
+    num::NonZeroUsize,
+    sync::Arc,
+};
+
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use rustc_hash::{FxBuildHasher, FxHashMap};
+
+#[cfg(feature = "locking")]
+use crate::archetype::new_locked_entity;
+use crate::{
+    Acquirable, Extractable,
+    archetype::{Entries, new_entity},
+};
+
+/// A thread-safe collection like [`Archetype`](crate::Archetype), with its entries
+/// split across independently locked shards.
+#[derive(Debug)]
+pub struct ShardedArchetype<Key: Copy + Eq + Hash, Base: Extractable> {
+    shards: Arc<[RwLock<Entries<Key, Base>>]>,
+    /// Shift selecting the shard index bits of a key hash.
+    shift: u32,
+}
+
+impl<Key: Copy + Eq + Hash, Base: Extractable> Default for ShardedArchetype<Key, Base> {
+    /// Create an archetype with four shards per available thread.
+    fn default() -> Self {
+        Self::with_shards(default_shard_count())
+    }
+}
+
+fn default_shard_count() -> usize {
+    std::thread::available_parallelism().map_or(1, NonZeroUsize::get) * 4
+}
+
+impl<Key: Copy + Eq + Hash, Base: Extractable> Clone for ShardedArchetype<Key, Base> {
+    fn clone(&self) -> Self {
+        Self {
+            shards: Arc::clone(&self.shards),
+            shift: self.shift,
+        }
+    }
+}
+
+impl<Key: Copy + Eq + Hash, Base: Extractable> ShardedArchetype<Key, Base> {
+    /// Create an archetype with `shards` shards, rounded up to a power of two.
+    pub fn with_shards(shards: usize) -> Self {
+        Self::from_map(shards, FxHashMap::default())
+    }
+
+    fn from_map(shards: usize, map: FxHashMap<Key, Acquirable<Base>>) -> Self {
+        let shards = shards.max(1).next_power_of_two();
+        let mut maps: Vec<_> = (0..shards).map(|_| FxHashMap::default()).collect();
+        let shift = u64::BITS - shards.trailing_zeros();
+        for (key, value) in map {
+            maps[shard_index(shift, &key)].insert(key, value);
+        }
+        Self {
+            shards: maps
+                .into_iter()
+                .map(|map| RwLock::new(Entries::new(map)))
+                .collect(),
+            shift,
+        }
+    }
+
+    /// The number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn insert<U: Extractable>(&self, key: Key, value: U) -> Acquirable<U> {
+        let (acquirable, insert) = new_entity::<U, Base>(value);
+        self.store(key, insert);
+        acquirable
+    }
+
+    /// Like [`insert`](Self::insert), but create the entity with
+    /// [`Acquirable::new_locked`], so its components can be modified through
+    /// [`Acquirable::write`].
+    #[cfg(feature = "locking")]
+    pub fn insert_locked<U: Extractable>(&self, key: Key, value: U) -> Acquirable<U> {
+        let (acquirable, insert) = new_locked_entity::<U, Base>(value);
+        self.store(key, insert);
+        acquirable
+    }
+
+    fn store(&self, key: Key, insert: Acquirable<Base>) {
+        let mut entries = self.shard(&key).write();
+        let replaced = entries.insert(key, insert);
+        // Dropped after the lock is released, as despawn hooks may access this
+        // archetype.
+        drop(entries);
+        drop(replaced);
+    }
+
+    pub fn get(&self, key: &Key) -> Option<Acquirable<Base>> {
+        let entries = self.shard(key).read();
+        entries.map.get(key).cloned()
+    }
+
+    pub fn remove(&self, key: &Key) -> Option<Acquirable<Base>> {
+        let mut entries = self.shard(key).write();
+        entries.remove(key)
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        let entries = self.shard(key).read();
+        entries.map.contains_key(key)
+    }
+
+    /// Count the entries, locking every shard.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Check whether there are no entries, locking every shard.
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    pub fn clear(&self) {
+        let mut shards = self.write_all();
+        let cleared: Vec<_> = shards.iter_mut().map(|entries| entries.take()).collect();
+        // Dropped after the locks are released, as in `insert`.
+        drop(shards);
+        drop(cleared);
+    }
+
+    /// Collect the entries whose entity contains `U`, extracted as `U`.
+    ///
+    /// See [`Archetype::iter_as`](crate::Archetype::iter_as). The entries are
+    /// collected with every shard locked, so they form a consistent snapshot.
+    pub fn iter_as<U: 'static>(&self) -> std::vec::IntoIter<(Key, Acquirable<U>)> {
+        let mut out = Vec::new();
+        for entries in self.read_all() {
+            entries.collect_as(&mut out);
+        }
+        out.into_iter()
+    }
+
+    /// Call `f` on every entry.
+    ///
+    /// `f` runs with every shard read locked, so it must not modify this archetype.
+    pub fn for_each(&self, mut f: impl FnMut(&Key, &Acquirable<Base>)) {
+        for (key, value) in self.read().iter() {
+            f(key, value);
+        }
+    }
+
+    /// Collect the entries for which `predicate` returns `true`.
+    ///
+    /// `predicate` runs with every shard read locked, so it must not modify this
+    /// archetype.
+    pub fn filter(
+        &self,
+        mut predicate: impl FnMut(&Key, &Acquirable<Base>) -> bool,
+    ) -> Vec<(Key, Acquirable<Base>)> {
+        self.read()
+            .iter()
+            .filter(|(key, value)| predicate(key, value))
+            .map(|(key, value)| (*key, value.clone()))
+            .collect()
+    }
+
+    /// Remove the entries for which `predicate` returns `false`.
+    ///
+    /// `predicate` runs with every shard write locked, so it must not access this
+    /// archetype. Removed entities are dropped after the locks are released.
+    pub fn retain(&self, mut predicate: impl FnMut(&Key, &Acquirable<Base>) -> bool) {
+        drop(self.drain_filter(|key, value| !predicate(key, value)));
+    }
+
+    /// Remove and return the entries for which `predicate` returns `true`.
+    ///
+    /// `predicate` runs with every shard write locked, so it must not access this
+    /// archetype.
+    pub fn drain_filter(
+        &self,
+        mut predicate: impl FnMut(&Key, &Acquirable<Base>) -> bool,
+    ) -> Vec<(Key, Acquirable<Base>)> {
+        let mut drained = Vec::new();
+        for mut entries in self.write_all() {
+            entries.drain_filter(&mut predicate, &mut drained);
+        }
+        drained
+    }
+
+    /// Read lock every shard, for a consistent view of the whole collection.
+    pub fn read(&self) -> ShardedReadGuard<'_, Key, Base> {
+        ShardedReadGuard {
+            archetype: self,
+            shards: self.read_all(),
+        }
+    }
+
+    fn shard(&self, key: &Key) -> &RwLock<Entries<Key, Base>> {
+        &self.shards[shard_index(self.shift, key)]
+    }
+
+    /// Lock every shard for reading, in shard order.
+    fn read_all(&self) -> Vec<RwLockReadGuard<'_, Entries<Key, Base>>> {
+        self.shards.iter().map(RwLock::read).collect()
+    }
+
+    /// Lock every shard for writing, in shard order.
+    fn write_all(&self) -> Vec<RwLockWriteGuard<'_, Entries<Key, Base>>> {
+        self.shards.iter().map(RwLock::write).collect()
+    }
+}
+
+fn shard_index<Key: Hash>(shift: u32, key: &Key) -> usize {
+    let hash = FxBuildHasher.hash_one(key);
+    // Skip the top 7 bits, which the map inside each shard uses to tag its entries.
+    (hash << 7).checked_shr(shift).unwrap_or(0) as usize
+}
+
+/// Read guard over every shard of a [`ShardedArchetype`].
+pub struct ShardedReadGuard<'a, Key: Copy + Eq + Hash, Base: Extractable> {
+    archetype: &'a ShardedArchetype<Key, Base>,
+    shards: Vec<RwLockReadGuard<'a, Entries<Key, Base>>>,
+}
+
+impl<Key: Copy + Eq + Hash, Base: Extractable> ShardedReadGuard<'_, Key, Base> {
+    pub fn get(&self, key: &Key) -> Option<&Acquirable<Base>> {
+        self.shards[shard_index(self.archetype.shift, key)]
+            .map
+            .get(key)
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|entries| entries.map.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|entries| entries.map.is_empty())
+    }
+
+    /// Iterate over every entry, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Acquirable<Base>)> {
+        self.shards.iter().flat_map(|entries| entries.map.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<Key, Base> serde::Serialize for ShardedArchetype<Key, Base>
+where
+    Key: Copy + Eq + Hash + serde::Serialize,
+    Base: Extractable,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let guard = self.read();
+        crate::archetype::serialize_entries(serializer, guard.len(), guard.iter())
+    }
+}
+
+/// Deserializes into the default number of shards.
+#[cfg(feature = "serde")]
+impl<'de, Key, Base> serde::Deserialize<'de> for ShardedArchetype<Key, Base>
+where
+    Key: Copy + Eq + Hash + serde::Deserialize<'de>,
+    Base: Extractable,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = crate::archetype::deserialize_entries(deserializer)?;
+        Ok(Self::from_map(default_shard_count(), map))
+    }
+}
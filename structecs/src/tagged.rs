@@ -0,0 +1,285 @@
+//! Serialization of entities as their concrete type, identified by a type tag.
+//!
+//! `Acquirable<T>` serializes as a plain `T`, which loses the concrete type of an
+//! entity stored behind one of its components. Types registered with a tag are
+//! written as a single-entry map `{ tag: value }` instead, and read back as the
+//! registered concrete type. This is how [`Archetype`](crate::Archetype) stores its
+//! entities, and can be used for individual fields with
+//! `#[serde(with = "structecs::tagged")]`.
+//!
+//! Derived types are registered with `#[extractable(tag = "...")]`. Other types,
+//! including instantiations of generic types, are registered by submitting a
+//! [`SerdeType`] with `inventory::submit!`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//! use structecs::*;
+//!
+//! #[derive(Extractable, Serialize, Deserialize)]
+//! #[extractable(tag = "entity")]
+//! struct Entity {
+//!     id: u32,
+//! }
+//!
+//! #[derive(Extractable, Serialize, Deserialize)]
+//! #[extractable(entity, tag = "player")]
+//! struct Player {
+//!     name: String,
+//!     entity: Entity,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Save {
+//!     #[serde(with = "structecs::tagged")]
+//!     focus: Acquirable<Entity>,
+//! }
+//!
+//! let player = Acquirable::new(Player {
+//!     name: "Alice".to_string(),
+//!     entity: Entity { id: 1 },
+//! });
+//! let save = Save { focus: player.extract::<Entity>().unwrap() };
+//!
+//! let json = serde_json::to_string(&save).unwrap();
+//! assert_eq!(json, r#"{"focus":{"player":{"name":"Alice","entity":{"id":1}}}}"#);
+//!
+//! let loaded: Save = serde_json::from_str(&json).unwrap();
+//! assert_eq!(loaded.focus.extract::<Player>().unwrap().name, "Alice");
+//! ```
+
+use std::{any::TypeId, fmt, marker::PhantomData, ptr::NonNull, sync::Arc, sync::LazyLock};
+
+use rustc_hash::FxHashMap;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{DeserializeOwned, DeserializeSeed, Error as _, MapAccess, Visitor},
+    ser::{Error as _, SerializeMap},
+};
+
+use crate::{Acquirable, Extractable, Extractor, entity::EntityData};
+
+/// Registration of a concrete type for tagged serialization.
+///
+/// Submitted to the inventory by `#[extractable(tag = "...")]`. Tags must be unique
+/// across the program.
+pub struct SerdeType {
+    pub(crate) tag: &'static str,
+    pub(crate) type_id: TypeId,
+    pub(crate) type_name: fn() -> &'static str,
+    pub(crate) serialize: for<'a> unsafe fn(&'a NonNull<u8>) -> &'a dyn erased_serde::Serialize,
+    pub(crate) deserialize:
+        fn(&mut dyn erased_serde::Deserializer<'_>) -> Result<Arc<EntityData>, erased_serde::Error>,
+    pub(crate) deserialize_into: unsafe fn(
+        &mut dyn erased_serde::Deserializer<'_>,
+        NonNull<u8>,
+    ) -> Result<(), erased_serde::Error>,
+    pub(crate) extractor: fn() -> &'static Extractor,
+}
+
+impl SerdeType {
+    /// Register `T` under `tag`.
+    pub const fn new<T: Extractable + Serialize + DeserializeOwned>(tag: &'static str) -> Self {
+        Self {
+            tag,
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>,
+            serialize: |data| {
+                // SAFETY: Only called with the data of an entity whose concrete type is T.
+                unsafe { data.cast::<T>().as_ref() }
+            },
+            deserialize: |deserializer| {
+                let value: T = erased_serde::deserialize(deserializer)?;
+                Ok(Arc::new(EntityData::new(
+                    value,
+                    crate::get_extractor::<T>(),
+                )))
+            },
+            deserialize_into: |deserializer, data| {
+                let value: T = erased_serde::deserialize(deserializer)?;
+                // SAFETY: The caller passes an uninitialized allocation for a T.
+                unsafe { data.cast::<T>().write(value) };
+                Ok(())
+            },
+            extractor: crate::get_extractor::<T>,
+        }
+    }
+
+    /// The tag identifying the type in serialized data.
+    #[inline]
+    pub fn tag(&self) -> &'static str {
+        self.tag
+    }
+
+    /// The `TypeId` of the registered type.
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+}
+
+inventory::collect!(SerdeType);
+
+struct SerdeTypes {
+    by_tag: FxHashMap<&'static str, &'static SerdeType>,
+    by_type: FxHashMap<TypeId, &'static SerdeType>,
+}
+
+static SERDE_TYPES: LazyLock<SerdeTypes> = LazyLock::new(|| {
+    let mut types = SerdeTypes {
+        by_tag: FxHashMap::default(),
+        by_type: FxHashMap::default(),
+    };
+    for serde_type in inventory::iter::<SerdeType> {
+        if let Some(previous) = types.by_tag.insert(serde_type.tag, serde_type) {
+            #[cfg(debug_assertions)]
+            if previous.type_id != serde_type.type_id {
+                panic!(
+                    "Serde tag \"{}\" is registered for both `{}` and `{}`",
+                    serde_type.tag,
+                    (previous.type_name)(),
+                    (serde_type.type_name)()
+                );
+            }
+            #[cfg(not(debug_assertions))]
+            let _ = previous;
+        }
+        types.by_type.insert(serde_type.type_id, serde_type);
+    }
+    types
+});
+
+/// Find the registration of the concrete type `type_id`.
+pub fn find_type(type_id: TypeId) -> Option<&'static SerdeType> {
+    SERDE_TYPES.by_type.get(&type_id).copied()
+}
+
+/// Find the registration for `tag`.
+pub fn find_tag(tag: &str) -> Option<&'static SerdeType> {
+    SERDE_TYPES.by_tag.get(tag).copied()
+}
+
+/// Serialize the entity behind `acquirable` as its concrete type, tagged.
+///
+/// Fails if the concrete type has no [`SerdeType`] registration.
+pub fn serialize<T: 'static, S: Serializer>(
+    acquirable: &Acquirable<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    Tagged(acquirable).serialize(serializer)
+}
+
+/// Deserialize a tagged entity, and extract `T` from it.
+///
+/// Fails if the tag is unknown, or the tagged type does not contain `T`.
+pub fn deserialize<'de, T: 'static, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Acquirable<T>, D::Error> {
+    TaggedSeed(PhantomData).deserialize(deserializer)
+}
+
+/// Serializes an entity as a single-entry map from its tag to its concrete value.
+pub(crate) struct Tagged<'a, T: 'static>(pub(crate) &'a Acquirable<T>);
+
+impl<T: 'static> Serialize for Tagged<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if crate::graph::is_serializing() {
+            return crate::graph::serialize_strong(&self.0.inner, serializer);
+        }
+        let inner = &self.0.inner;
+        let serde_type = find_type(inner.extractor.type_id).ok_or_else(|| {
+            S::Error::custom(format_args!(
+                "type `{}` has no serde tag; register it with #[extractable(tag = \"...\")]",
+                inner.extractor.type_name
+            ))
+        })?;
+        #[cfg(feature = "locking")]
+        let _guard = inner.lock.read_recursive();
+        // SAFETY: `serde_type` was registered for the concrete type of this entity.
+        let value = unsafe { (serde_type.serialize)(&inner.data) };
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(serde_type.tag, value)?;
+        map.end()
+    }
+}
+
+/// Deserializes what [`Tagged`] serializes, extracting `T` from the entity.
+pub(crate) struct TaggedSeed<T>(pub(crate) PhantomData<T>);
+
+impl<'de, T: 'static> DeserializeSeed<'de> for TaggedSeed<T> {
+    type Value = Acquirable<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        if crate::graph::is_deserializing() {
+            return crate::graph::deserialize_strong(deserializer);
+        }
+        deserializer.deserialize_map(TaggedVisitor(PhantomData))
+    }
+}
+
+struct TaggedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: 'static> Visitor<'de> for TaggedVisitor<T> {
+    type Value = Acquirable<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map with a single serde tag as key")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let tag: std::borrow::Cow<'de, str> = map
+            .next_key()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let serde_type = find_tag(&tag)
+            .ok_or_else(|| A::Error::custom(format_args!("unknown serde tag \"{}\"", tag)))?;
+        let data = map.next_value_seed(EntitySeed(serde_type))?;
+        if map.next_key::<serde::de::IgnoredAny>()?.is_some() {
+            return Err(A::Error::invalid_length(2, &self));
+        }
+        data.extract::<T>().ok_or_else(|| {
+            A::Error::custom(format_args!(
+                "type `{}` tagged \"{}\" does not contain `{}`",
+                (serde_type.type_name)(),
+                serde_type.tag,
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+}
+
+struct EntitySeed(&'static SerdeType);
+
+impl<'de> DeserializeSeed<'de> for EntitySeed {
+    type Value = Arc<EntityData>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.0.deserialize)(&mut erased).map_err(D::Error::custom)
+    }
+}
+
+/// Serializes the component, or a reference to the entity inside a [`graph`](crate::graph).
+impl<T: 'static + Serialize> Serialize for Acquirable<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if crate::graph::is_serializing() {
+            return crate::graph::serialize_strong(&self.inner, serializer);
+        }
+        #[cfg(not(feature = "locking"))]
+        let target = &**self;
+        #[cfg(feature = "locking")]
+        let target = &*self.read_recursive();
+        target.serialize(serializer)
+    }
+}
+
+/// Creates a new entity from the component, or resolves a reference inside a
+/// [`graph`](crate::graph).
+impl<'de, T: Extractable + Deserialize<'de>> Deserialize<'de> for Acquirable<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if crate::graph::is_deserializing() {
+            return crate::graph::deserialize_strong(deserializer);
+        }
+        T::deserialize(deserializer).map(Acquirable::new)
+    }
+}
@@ -429,3 +429,494 @@ fn test_archetype_base_type_retrieval() {
     let base2 = archetype.get(&2).unwrap();
     assert_eq!(base2.id, 200);
 }
+
+#[derive(Extractable, Debug)]
+struct QueryEntity {
+    id: u32,
+}
+
+#[derive(Extractable, Debug)]
+#[extractable(entity)]
+struct QueryPlayer {
+    name: String,
+    entity: QueryEntity,
+}
+
+#[derive(Extractable, Debug)]
+#[extractable(entity)]
+struct QueryZombie {
+    entity: QueryEntity,
+}
+
+fn new_query_archetype() -> Archetype<u32, QueryEntity> {
+    let archetype = Archetype::default();
+    for id in 0..6 {
+        if id % 2 == 0 {
+            archetype.insert(
+                id,
+                QueryPlayer {
+                    name: format!("Player{id}"),
+                    entity: QueryEntity { id },
+                },
+            );
+        } else {
+            archetype.insert(
+                id,
+                QueryZombie {
+                    entity: QueryEntity { id },
+                },
+            );
+        }
+    }
+    archetype
+}
+
+/// Test iter_as yields only entities containing the requested type
+#[test]
+fn test_archetype_iter_as() {
+    let archetype = new_query_archetype();
+
+    let mut players: Vec<_> = archetype.iter_as::<QueryPlayer>().collect();
+    players.sort_by_key(|(key, _)| *key);
+    assert_eq!(players.len(), 3);
+    for (key, player) in &players {
+        assert_eq!(player.name, format!("Player{key}"));
+    }
+
+    assert_eq!(archetype.iter_as::<QueryEntity>().count(), 6);
+    assert_eq!(archetype.iter_as::<u64>().count(), 0);
+}
+
+/// Test the archetype can be modified while iterating with iter_as
+#[test]
+fn test_archetype_iter_as_allows_modification() {
+    let archetype = new_query_archetype();
+
+    for (key, _) in archetype.iter_as::<QueryZombie>() {
+        archetype.remove(&key);
+    }
+    assert_eq!(archetype.len(), 3);
+    assert_eq!(archetype.iter_as::<QueryZombie>().count(), 0);
+}
+
+/// Test for_each visits every entry
+#[test]
+fn test_archetype_for_each() {
+    let archetype = new_query_archetype();
+
+    let mut sum = 0;
+    archetype.for_each(|key, entity| {
+        assert_eq!(*key, entity.id);
+        sum += entity.id;
+    });
+    assert_eq!(sum, 15);
+}
+
+/// Test filter returns matching entries without removing them
+#[test]
+fn test_archetype_filter() {
+    let archetype = new_query_archetype();
+
+    let mut found = archetype.filter(|_, entity| entity.is::<QueryZombie>() && entity.id > 1);
+    found.sort_by_key(|(key, _)| *key);
+    let keys: Vec<_> = found.iter().map(|(key, _)| *key).collect();
+    assert_eq!(keys, [3, 5]);
+    assert_eq!(archetype.len(), 6);
+}
+
+/// Test retain removes entries the predicate rejects
+#[test]
+fn test_archetype_retain() {
+    let archetype = new_query_archetype();
+
+    archetype.retain(|_, entity| entity.is::<QueryPlayer>());
+    assert_eq!(archetype.len(), 3);
+    assert!(archetype.iter_as::<QueryZombie>().next().is_none());
+}
+
+/// Test drain_filter removes and returns matching entries
+#[test]
+fn test_archetype_drain_filter() {
+    let archetype = new_query_archetype();
+
+    let drained = archetype.drain_filter(|key, _| *key < 2);
+    assert_eq!(drained.len(), 2);
+    assert_eq!(archetype.len(), 4);
+    for (key, entity) in drained {
+        assert_eq!(key, entity.id);
+        assert!(!archetype.contains_key(&key));
+    }
+}
+
+/// Test entities removed by retain are dropped once no longer referenced
+#[test]
+fn test_archetype_retain_drops_entities() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Extractable)]
+    struct Counted {
+        id: u32,
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let archetype: Archetype<u32, Counted> = Archetype::default();
+    for id in 0..4 {
+        archetype.insert(id, Counted { id });
+    }
+    let kept = archetype.get(&0).unwrap();
+
+    archetype.retain(|_, _| false);
+    assert!(archetype.is_empty());
+    assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+
+    drop(kept);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 4);
+}
+
+/// Test iter_as stays consistent through overwrites and removals
+#[test]
+fn test_archetype_iter_as_after_overwrite() {
+    let archetype = new_query_archetype();
+
+    // Replace a zombie with a player, and a player with another player
+    archetype.insert(
+        1,
+        QueryPlayer {
+            name: "Player1".to_string(),
+            entity: QueryEntity { id: 1 },
+        },
+    );
+    archetype.insert(
+        2,
+        QueryPlayer {
+            name: "Player2".to_string(),
+            entity: QueryEntity { id: 2 },
+        },
+    );
+    archetype.remove(&4);
+
+    let mut keys: Vec<_> = archetype
+        .iter_as::<QueryPlayer>()
+        .map(|(key, _)| key)
+        .collect();
+    keys.sort();
+    assert_eq!(keys, [0, 1, 2]);
+    assert_eq!(archetype.iter_as::<QueryZombie>().count(), 2);
+    assert_eq!(archetype.iter_as::<QueryEntity>().count(), 5);
+
+    archetype.clear();
+    assert_eq!(archetype.iter_as::<QueryEntity>().count(), 0);
+}
+
+/// Test iter_as sees entries modified directly through write()
+#[test]
+fn test_archetype_iter_as_after_write() {
+    let archetype = new_query_archetype();
+    let player = archetype.get(&0).unwrap();
+
+    {
+        let mut map = archetype.write();
+        map.remove(&1);
+        map.insert(10, player);
+    }
+
+    let mut keys: Vec<_> = archetype
+        .iter_as::<QueryPlayer>()
+        .map(|(key, _)| key)
+        .collect();
+    keys.sort();
+    assert_eq!(keys, [0, 2, 4, 10]);
+    assert_eq!(archetype.iter_as::<QueryZombie>().count(), 2);
+}
+
+/// Test iter_as sees entries modified through the map returned by inner()
+#[test]
+fn test_archetype_iter_as_after_inner() {
+    let archetype = new_query_archetype();
+    let player = archetype.get(&0).unwrap();
+
+    // Modify the map after the index was last used, without going through the archetype
+    assert_eq!(archetype.iter_as::<QueryPlayer>().count(), 3);
+    archetype.inner().write().remove(&1);
+    archetype.inner().write().insert(10, player);
+
+    let mut keys: Vec<_> = archetype
+        .iter_as::<QueryPlayer>()
+        .map(|(key, _)| key)
+        .collect();
+    keys.sort();
+    assert_eq!(keys, [0, 2, 4, 10]);
+    assert_eq!(archetype.iter_as::<QueryZombie>().count(), 2);
+}
+
+/// Test iter_as over enum entities only yields the matching variants
+#[test]
+fn test_archetype_iter_as_enum_variants() {
+    #[derive(Extractable)]
+    struct Position {
+        x: i32,
+    }
+
+    #[derive(Extractable)]
+    struct Velocity {
+        dx: i32,
+    }
+
+    #[derive(Extractable)]
+    enum Body {
+        Static(#[extractable] Position),
+        Moving(#[extractable] Position, #[extractable] Velocity),
+    }
+
+    #[derive(Extractable)]
+    #[extractable(body)]
+    struct Object {
+        body: Body,
+    }
+
+    let archetype: Archetype<u32, Object> = Archetype::default();
+    archetype.insert(
+        0,
+        Object {
+            body: Body::Static(Position { x: 0 }),
+        },
+    );
+    archetype.insert(
+        1,
+        Object {
+            body: Body::Moving(Position { x: 1 }, Velocity { dx: 1 }),
+        },
+    );
+
+    assert_eq!(archetype.iter_as::<Position>().count(), 2);
+    let moving: Vec<_> = archetype.iter_as::<Velocity>().collect();
+    assert_eq!(moving.len(), 1);
+    assert_eq!(moving[0].0, 1);
+}
+
+fn describe<Base>(event: &ArchetypeEvent<u32, Base>) -> String {
+    match event {
+        ArchetypeEvent::Inserted { key, .. } => format!("inserted {key}"),
+        ArchetypeEvent::Replaced { key, .. } => format!("replaced {key}"),
+        ArchetypeEvent::Removed { key, .. } => format!("removed {key}"),
+    }
+}
+
+/// Test observers receive insertions, overwrites and removals in order
+#[test]
+fn test_archetype_subscribe() {
+    use std::sync::{Arc, Mutex};
+
+    let archetype: Archetype<u32, QueryEntity> = Archetype::default();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let log = events.clone();
+    archetype.subscribe(move |event| {
+        let old_id = match event {
+            ArchetypeEvent::Replaced { old, .. } => Some(old.id),
+            _ => None,
+        };
+        log.lock()
+            .unwrap()
+            .push(format!("{} {old_id:?}", describe(event)));
+    });
+
+    archetype.insert(1, QueryEntity { id: 1 });
+    archetype.insert(1, QueryEntity { id: 10 });
+    archetype.insert(2, QueryEntity { id: 2 });
+    archetype.remove(&1);
+    archetype.remove(&1);
+    archetype.clear();
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "inserted 1 None",
+            "replaced 1 Some(1)",
+            "inserted 2 None",
+            "removed 1 None",
+            "removed 2 None",
+        ]
+    );
+}
+
+/// Test retain and drain_filter notify removals
+#[test]
+fn test_archetype_subscribe_bulk_removal() {
+    use std::sync::{Arc, Mutex};
+
+    let archetype = new_query_archetype();
+    let removed = Arc::new(Mutex::new(Vec::new()));
+    let log = removed.clone();
+    archetype.subscribe(move |event| {
+        if let ArchetypeEvent::Removed { key, .. } = event {
+            log.lock().unwrap().push(*key);
+        }
+    });
+
+    archetype.drain_filter(|key, _| *key < 2);
+    archetype.retain(|key, _| *key < 4);
+
+    let mut removed = removed.lock().unwrap().clone();
+    removed.sort();
+    assert_eq!(removed, [0, 1, 4, 5]);
+}
+
+/// Test subscribe_as only sees entities containing the observed type
+#[test]
+fn test_archetype_subscribe_as() {
+    use std::sync::{Arc, Mutex};
+
+    let archetype: Archetype<u32, QueryEntity> = Archetype::default();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let log = events.clone();
+    archetype.subscribe_as::<QueryPlayer>(move |event| {
+        log.lock().unwrap().push(describe(event));
+    });
+
+    let player = || QueryPlayer {
+        name: "Alice".to_string(),
+        entity: QueryEntity { id: 1 },
+    };
+    let zombie = || QueryZombie {
+        entity: QueryEntity { id: 1 },
+    };
+
+    archetype.insert(1, zombie());
+    archetype.insert(1, player());
+    archetype.insert(1, player());
+    archetype.insert(1, zombie());
+    archetype.insert(1, zombie());
+    archetype.insert(2, player());
+    archetype.remove(&2);
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "inserted 1",
+            "replaced 1",
+            "removed 1",
+            "inserted 2",
+            "removed 2"
+        ]
+    );
+}
+
+/// Test observers can modify the archetype without deadlocking
+#[test]
+fn test_archetype_subscribe_reentrant() {
+    use std::sync::{Arc, Mutex};
+
+    let archetype: Archetype<u32, QueryEntity> = Archetype::default();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let log = events.clone();
+    let inner = archetype.clone();
+    archetype.subscribe(move |event| {
+        log.lock().unwrap().push(describe(event));
+        // Mirror every insertion under a second key
+        if let ArchetypeEvent::Inserted { key, value } = event
+            && *key < 100
+        {
+            assert!(inner.contains_key(key));
+            inner.insert(key + 100, QueryEntity { id: value.id });
+        }
+    });
+
+    archetype.insert(1, QueryEntity { id: 1 });
+    assert_eq!(archetype.get(&101).unwrap().id, 1);
+    assert_eq!(*events.lock().unwrap(), ["inserted 1", "inserted 101"]);
+}
+
+/// Test unsubscribed observers are no longer called
+#[test]
+fn test_archetype_unsubscribe() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let archetype: Archetype<u32, QueryEntity> = Archetype::default();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let id = archetype.subscribe(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+
+    archetype.insert(1, QueryEntity { id: 1 });
+    assert!(archetype.unsubscribe(id));
+    assert!(!archetype.unsubscribe(id));
+    archetype.insert(2, QueryEntity { id: 2 });
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+/// Test a panicking observer does not stop later events from being delivered
+#[test]
+fn test_archetype_subscribe_panic() {
+    use std::sync::{Arc, Mutex};
+
+    let archetype: Archetype<u32, QueryEntity> = Archetype::default();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let log = events.clone();
+    archetype.subscribe(move |event| {
+        if event.key() == 1 {
+            panic!("observer failed");
+        }
+        log.lock().unwrap().push(describe(event));
+    });
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        archetype.insert(1, QueryEntity { id: 1 });
+    }));
+    assert!(result.is_err());
+    assert!(archetype.contains_key(&1));
+
+    archetype.insert(2, QueryEntity { id: 2 });
+    assert_eq!(*events.lock().unwrap(), ["inserted 2"]);
+}
+
+/// Test despawn hooks of replaced and cleared entities can modify the archetype
+#[test]
+fn test_archetype_despawn_hook_modifies_archetype() {
+    #[derive(Extractable, Debug)]
+    struct Entity {
+        id: u32,
+    }
+
+    let archetype = Archetype::<u32, Entity>::default();
+    let despawn_removes = |key: u32, target: u32| {
+        let archetype_ref = archetype.clone();
+        archetype
+            .insert(key, Entity { id: key })
+            .on_destroy(move |_| drop(archetype_ref.remove(&target)));
+    };
+
+    despawn_removes(1, 2);
+    archetype.insert(2, Entity { id: 2 });
+    archetype.insert(1, Entity { id: 10 });
+    assert!(!archetype.contains_key(&2));
+    assert_eq!(archetype.get(&1).unwrap().id, 10);
+
+    despawn_removes(3, 4);
+    archetype.insert(4, Entity { id: 4 });
+    archetype.clear();
+    assert!(archetype.is_empty());
+}
+
+/// Test inserting a different instantiation of a generic base panics at runtime
+#[test]
+#[should_panic(expected = "as an extractable component")]
+fn test_archetype_insert_generic_instantiation_mismatch() {
+    #[derive(Extractable)]
+    struct Tagged<T> {
+        value: T,
+    }
+
+    let archetype = Archetype::<u32, Tagged<u64>>::default();
+    archetype.insert(1, Tagged { value: 1u32 });
+}
@@ -84,6 +84,81 @@ fn test_ptr_eq() {
     assert!(!entity1.ptr_eq(&entity3));
 }
 
+#[cfg(feature = "entity-id")]
+#[test]
+fn test_entity_id() {
+    #[derive(Extractable)]
+    struct Entity {
+        id: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(entity)]
+    struct Player {
+        entity: Entity,
+        name: String,
+    }
+
+    let player = Acquirable::new(Player {
+        entity: Entity { id: 1 },
+        name: "Alice".to_string(),
+    });
+    let other = Acquirable::new(Entity { id: 1 });
+
+    // Shared by clones and extracted components
+    let entity = player.extract::<Entity>().unwrap();
+    assert_eq!(player.entity_id(), player.clone().entity_id());
+    assert_eq!(player.entity_id(), entity.entity_id());
+
+    // Allocated in increasing order
+    assert!(player.entity_id() < other.entity_id());
+    assert_eq!(
+        other.entity_id().to_string(),
+        other.entity_id().get().to_string()
+    );
+}
+
+#[cfg(feature = "entity-id")]
+#[test]
+fn test_entity_id_not_reused() {
+    use std::collections::HashSet;
+
+    #[derive(Extractable)]
+    struct Entity {
+        id: u32,
+    }
+
+    let mut ids = HashSet::new();
+    for id in 0..100 {
+        // Each entity is freed before the next one is allocated, so the
+        // allocation is likely to be reused while the ID is not.
+        let entity = Acquirable::new(Entity { id });
+        assert!(ids.insert(entity.entity_id()));
+    }
+}
+
+#[cfg(feature = "entity-id")]
+#[test]
+fn test_weak_entity_id() {
+    #[derive(Extractable)]
+    struct Entity {
+        id: u32,
+    }
+
+    let entity = Acquirable::new(Entity { id: 42 });
+    let id = entity.entity_id();
+    let weak = entity.downgrade();
+
+    assert_eq!(weak.entity_id(), Some(id));
+    assert_eq!(weak.clone().entity_id(), Some(id));
+
+    drop(entity);
+    assert!(weak.upgrade().is_none());
+    assert_eq!(weak.entity_id(), Some(id));
+
+    assert_eq!(WeakAcquirable::<Entity>::default().entity_id(), None);
+}
+
 #[test]
 fn test_reference_counting() {
     #[derive(Extractable)]
@@ -268,3 +343,142 @@ fn test_handler_with_multiple_entity_types() {
     assert_eq!(handler_a.call(&entity_a, ()), "A");
     assert_eq!(handler_b.call(&entity_b, ()), 100);
 }
+
+#[test]
+fn test_concrete_type_identity() {
+    use std::any::TypeId;
+
+    #[derive(Extractable)]
+    struct Entity {
+        id: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(entity)]
+    struct Player {
+        entity: Entity,
+        name: String,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(entity)]
+    struct Zombie {
+        entity: Entity,
+    }
+
+    let entities: Vec<Acquirable<Entity>> = vec![
+        Acquirable::new(Player {
+            entity: Entity { id: 1 },
+            name: "Steve".to_string(),
+        })
+        .extract()
+        .unwrap(),
+        Acquirable::new(Zombie {
+            entity: Entity { id: 2 },
+        })
+        .extract()
+        .unwrap(),
+        Acquirable::new(Entity { id: 3 }),
+    ];
+
+    assert_eq!(entities[0].concrete_type_id(), TypeId::of::<Player>());
+    assert_eq!(entities[1].concrete_type_id(), TypeId::of::<Zombie>());
+    assert_eq!(entities[2].concrete_type_id(), TypeId::of::<Entity>());
+
+    assert_eq!(
+        entities[0].concrete_type_name(),
+        std::any::type_name::<Player>()
+    );
+    assert_eq!(
+        entities[1].concrete_type_name(),
+        std::any::type_name::<Zombie>()
+    );
+
+    assert!(entities[0].is::<Player>());
+    assert!(!entities[0].is::<Zombie>());
+    assert!(!entities[0].is::<Entity>());
+    assert!(entities[1].is::<Zombie>());
+    assert!(entities[2].is::<Entity>());
+}
+
+#[test]
+fn test_handler_registry_dispatch() {
+    #[derive(Extractable)]
+    struct Entity {
+        id: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(entity)]
+    struct LivingEntity {
+        entity: Entity,
+        health: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(living)]
+    struct Player {
+        living: LivingEntity,
+        name: String,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(living)]
+    struct Zombie {
+        living: LivingEntity,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(entity)]
+    struct Item {
+        entity: Entity,
+    }
+
+    let mut registry = HandlerRegistry::<Entity, u32, String>::new();
+    registry
+        .register::<Player>(|player, damage| format!("player {} took {}", player.name, damage))
+        .register::<LivingEntity>(|living, damage| {
+            format!("living {} took {}", living.health, damage)
+        });
+    assert_eq!(registry.len(), 2);
+    assert!(registry.contains::<Player>());
+    assert!(!registry.contains::<Zombie>());
+
+    let player: Acquirable<Entity> = Acquirable::new(Player {
+        living: LivingEntity {
+            entity: Entity { id: 1 },
+            health: 20,
+        },
+        name: "Steve".to_string(),
+    })
+    .extract()
+    .unwrap();
+    let zombie: Acquirable<Entity> = Acquirable::new(Zombie {
+        living: LivingEntity {
+            entity: Entity { id: 2 },
+            health: 10,
+        },
+    })
+    .extract()
+    .unwrap();
+    let item: Acquirable<Entity> = Acquirable::new(Item {
+        entity: Entity { id: 3 },
+    })
+    .extract()
+    .unwrap();
+
+    // Exact concrete type
+    assert_eq!(registry.call(&player, 5).unwrap(), "player Steve took 5");
+    // Nearest registered ancestor
+    assert_eq!(registry.call(&zombie, 3).unwrap(), "living 10 took 3");
+    // Nothing matches
+    assert!(registry.call(&item, 1).is_none());
+
+    // A closer handler takes precedence over a farther one
+    registry.register::<Entity>(|entity, _| format!("entity {}", entity.id));
+    assert_eq!(registry.call(&zombie, 3).unwrap(), "living 10 took 3");
+    assert_eq!(registry.call(&item, 1).unwrap(), "entity 3");
+
+    assert!(registry.unregister::<LivingEntity>().is_some());
+    assert_eq!(registry.call(&zombie, 3).unwrap(), "entity 2");
+}
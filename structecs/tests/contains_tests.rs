@@ -0,0 +1,136 @@
+#![allow(dead_code)]
+
+use structecs::{
+    contains::{Field, Here},
+    *,
+};
+
+#[derive(Extractable, Debug, PartialEq)]
+struct Health {
+    current: u32,
+}
+
+#[derive(Extractable)]
+#[extractable(health)]
+struct LivingEntity {
+    id: u32,
+    health: Health,
+}
+
+#[derive(Extractable)]
+#[extractable(living)]
+struct Player {
+    name: String,
+    living: LivingEntity,
+}
+
+#[derive(Extractable)]
+#[extractable(0)]
+struct Zombie(LivingEntity, u32);
+
+fn player() -> Acquirable<Player> {
+    Acquirable::new(Player {
+        name: "Alice".to_string(),
+        living: LivingEntity {
+            id: 1,
+            health: Health { current: 20 },
+        },
+    })
+}
+
+fn damage<E: Contains<Health, P>, P>(entity: &Acquirable<E>) -> u32 {
+    entity.get::<Health, _>().current
+}
+
+/// Test get returns the same components as extract
+#[test]
+fn test_get_matches_extract() {
+    let player = player();
+
+    let living: Acquirable<LivingEntity> = player.get();
+    assert_eq!(living.id, 1);
+    assert!(living.ptr_eq(&player));
+
+    let health = player.get::<Health, _>();
+    let extracted = player.extract::<Health>().unwrap();
+    assert_eq!(*health, *extracted);
+    assert_eq!(health.path(), extracted.path());
+
+    assert_eq!(player.get::<Player, _>().name, "Alice");
+}
+
+/// Test get from a component extracted from a larger entity
+#[test]
+fn test_get_from_extracted_component() {
+    let player = player();
+    let living = player.extract::<LivingEntity>().unwrap();
+
+    assert_eq!(living.get::<Health, _>().current, 20);
+    assert_eq!(living.get::<Health, _>().path(), ["living", "health"]);
+}
+
+/// Test generic functions bounded on Contains accept every containing type
+#[test]
+fn test_contains_bound() {
+    let zombie = Acquirable::new(Zombie(
+        LivingEntity {
+            id: 2,
+            health: Health { current: 5 },
+        },
+        0,
+    ));
+
+    assert_eq!(damage(&player()), 20);
+    assert_eq!(damage(&zombie), 5);
+    assert_eq!(damage(&zombie.get::<LivingEntity, _>()), 5);
+}
+
+/// Test duplicated components are told apart by their path
+#[test]
+fn test_get_duplicates_by_path() {
+    #[derive(Extractable)]
+    #[extractable(main_hand, off_hand, allow_duplicates = true)]
+    struct Hands {
+        main_hand: Health,
+        off_hand: Health,
+    }
+
+    let hands = Acquirable::new(Hands {
+        main_hand: Health { current: 1 },
+        off_hand: Health { current: 2 },
+    });
+
+    assert_eq!(hands.get::<Health, Field<1, Here>>().current, 1);
+    assert_eq!(hands.get::<Health, Field<2, Here>>().current, 2);
+}
+
+/// Test Contains on generic types and leaf fields
+#[test]
+fn test_contains_generic_and_leaf() {
+    struct Position {
+        x: i32,
+    }
+
+    #[derive(Extractable)]
+    struct Wrapper<T: Extractable> {
+        #[extractable(flatten)]
+        inner: T,
+        #[extract]
+        position: Position,
+        #[extractable]
+        living: LivingEntity,
+    }
+
+    let wrapper = Acquirable::new(Wrapper {
+        inner: Health { current: 7 },
+        position: Position { x: 3 },
+        living: LivingEntity {
+            id: 4,
+            health: Health { current: 9 },
+        },
+    });
+
+    assert_eq!(wrapper.get::<Health, Field<1, Here>>().current, 7);
+    assert_eq!(wrapper.get::<Position, _>().x, 3);
+    assert_eq!(wrapper.get::<LivingEntity, _>().id, 4);
+}
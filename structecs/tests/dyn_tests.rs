@@ -0,0 +1,200 @@
+#![allow(dead_code)]
+
+use std::any::TypeId;
+
+use structecs::*;
+
+trait Tickable: Send + Sync {
+    fn tick(&self) -> String;
+}
+
+trait Damageable {
+    fn health(&self) -> u32;
+}
+
+#[derive(Extractable)]
+#[extractable(dyn Tickable)]
+struct Entity {
+    id: u32,
+}
+
+impl Tickable for Entity {
+    fn tick(&self) -> String {
+        format!("entity {}", self.id)
+    }
+}
+
+#[derive(Extractable)]
+#[extractable(entity, dyn Tickable, dyn Damageable)]
+struct Zombie {
+    entity: Entity,
+    health: u32,
+}
+
+impl Tickable for Zombie {
+    fn tick(&self) -> String {
+        format!("zombie {}", self.entity.id)
+    }
+}
+
+impl Damageable for Zombie {
+    fn health(&self) -> u32 {
+        self.health
+    }
+}
+
+#[derive(Extractable)]
+#[extractable(entity)]
+struct Item {
+    entity: Entity,
+}
+
+fn zombie(id: u32, health: u32) -> Acquirable<Zombie> {
+    Acquirable::new(Zombie {
+        entity: Entity { id },
+        health,
+    })
+}
+
+/// Test the trait object is the concrete type, whichever component it is extracted from
+#[test]
+fn test_extract_dyn_uses_concrete_type() {
+    let zombie = zombie(1, 20);
+    let entity = zombie.extract::<Entity>().unwrap();
+
+    assert_eq!(
+        zombie.extract_dyn::<dyn Tickable>().unwrap().tick(),
+        "zombie 1"
+    );
+    assert_eq!(
+        entity.extract_dyn::<dyn Tickable>().unwrap().tick(),
+        "zombie 1"
+    );
+    assert_eq!(entity.extract_dyn::<dyn Damageable>().unwrap().health(), 20);
+
+    let entity = Acquirable::new(Entity { id: 2 });
+    assert_eq!(
+        entity.extract_dyn::<dyn Tickable>().unwrap().tick(),
+        "entity 2"
+    );
+}
+
+/// Test traits are not inherited from components implementing them
+#[test]
+fn test_extract_dyn_not_listed() {
+    let item = Acquirable::new(Item {
+        entity: Entity { id: 1 },
+    });
+    assert!(item.extract_dyn::<dyn Tickable>().is_none());
+    assert!(item.extract_dyn::<dyn Damageable>().is_none());
+
+    let entity = Acquirable::new(Entity { id: 2 });
+    assert!(entity.extract_dyn::<dyn Damageable>().is_none());
+}
+
+/// Test AcquirableDyn keeps the entity alive
+#[test]
+fn test_acquirable_dyn_keeps_entity_alive() {
+    let zombie = zombie(1, 20);
+    let weak = zombie.downgrade();
+    let tickable = zombie.extract_dyn::<dyn Tickable>().unwrap();
+
+    drop(zombie);
+    assert!(weak.upgrade().is_some());
+    assert_eq!(tickable.clone().tick(), "zombie 1");
+
+    drop(tickable);
+    assert!(weak.upgrade().is_none());
+}
+
+/// Test converting between trait objects and components
+#[test]
+fn test_acquirable_dyn_conversions() {
+    let zombie = zombie(1, 20);
+    let tickable = zombie.extract_dyn::<dyn Tickable>().unwrap();
+
+    assert!(tickable.ptr_eq(&zombie));
+    assert_eq!(tickable.concrete_type_id(), TypeId::of::<Zombie>());
+    assert_eq!(tickable.extract::<Entity>().unwrap().id, 1);
+    assert_eq!(
+        tickable.extract_dyn::<dyn Damageable>().unwrap().health(),
+        20
+    );
+    #[cfg(feature = "entity-id")]
+    assert_eq!(tickable.entity_id(), zombie.entity_id());
+}
+
+/// Test trait objects are listed in the registry
+#[test]
+fn test_extractor_implements() {
+    let zombie = registry::find_type(TypeId::of::<Zombie>()).unwrap();
+    assert!(zombie.implements(TypeId::of::<dyn Tickable>()));
+    assert!(zombie.implements(TypeId::of::<dyn Damageable>()));
+
+    let item = registry::find_type(TypeId::of::<Item>()).unwrap();
+    assert!(!item.implements(TypeId::of::<dyn Tickable>()));
+
+    let damageable: Vec<_> = registry::types_implementing(TypeId::of::<dyn Damageable>())
+        .iter()
+        .map(|extractor| extractor.type_id())
+        .collect();
+    assert!(damageable.contains(&TypeId::of::<Zombie>()));
+    assert!(!damageable.contains(&TypeId::of::<Entity>()));
+}
+
+/// Test trait objects on generic types and enums
+#[test]
+fn test_extract_dyn_generic_and_enum() {
+    #[derive(Extractable)]
+    #[extractable(dyn Damageable)]
+    struct Wrapper<T> {
+        value: T,
+    }
+
+    impl<T> Damageable for Wrapper<T> {
+        fn health(&self) -> u32 {
+            size_of::<T>() as u32
+        }
+    }
+
+    #[derive(Extractable)]
+    #[extractable(dyn Damageable)]
+    enum Block {
+        Air,
+        Stone(u32),
+    }
+
+    impl Damageable for Block {
+        fn health(&self) -> u32 {
+            match self {
+                Block::Air => 0,
+                Block::Stone(hardness) => *hardness,
+            }
+        }
+    }
+
+    let wrapper = Acquirable::new(Wrapper { value: 0u64 });
+    assert_eq!(wrapper.extract_dyn::<dyn Damageable>().unwrap().health(), 8);
+
+    let block = Acquirable::new(Block::Stone(3));
+    assert_eq!(block.extract_dyn::<dyn Damageable>().unwrap().health(), 3);
+}
+
+/// Test AcquirableDyn can be sent across threads when the trait is Send + Sync
+#[test]
+fn test_acquirable_dyn_send() {
+    let tickables: Vec<AcquirableDyn<dyn Tickable>> = (0..4)
+        .map(|id| zombie(id, 20).extract_dyn().unwrap())
+        .collect();
+
+    let handle = std::thread::spawn(move || {
+        tickables
+            .iter()
+            .map(|tickable| tickable.tick())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(
+        handle.join().unwrap(),
+        ["zombie 0", "zombie 1", "zombie 2", "zombie 3"]
+    );
+}
@@ -293,3 +293,173 @@ fn test_entity_data_zero_sized() {
 
     assert_eq!(*extracted, ZeroSized);
 }
+
+/// Test the derive's on_destroy hook runs before the entity is dropped
+#[test]
+fn test_entity_data_on_destroy_derive() {
+    use std::sync::Mutex;
+
+    static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn despawn(player: &mut DespawnPlayer) {
+        EVENTS
+            .lock()
+            .unwrap()
+            .push(format!("despawn {}", player.name));
+        player.name.clear();
+    }
+
+    #[derive(Extractable)]
+    #[extractable(on_destroy = despawn)]
+    struct DespawnPlayer {
+        name: String,
+        tracker: DropTracker,
+    }
+
+    struct DropTracker;
+
+    impl Drop for DropTracker {
+        fn drop(&mut self) {
+            EVENTS.lock().unwrap().push("drop".to_string());
+        }
+    }
+
+    let player = Acquirable::new(DespawnPlayer {
+        name: "Alice".to_string(),
+        tracker: DropTracker,
+    });
+    let clone = player.clone();
+    let weak = player.downgrade();
+
+    drop(player);
+    assert!(EVENTS.lock().unwrap().is_empty());
+
+    drop(clone);
+    assert_eq!(*EVENTS.lock().unwrap(), ["despawn Alice", "drop"]);
+    assert!(weak.upgrade().is_none());
+}
+
+/// Test per-instance and per-type hooks run in order, with the component they were registered on
+#[test]
+fn test_entity_data_on_destroy_order() {
+    use std::sync::Mutex;
+
+    static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn despawn(_: &mut Ordered) {
+        EVENTS.lock().unwrap().push("derive".to_string());
+    }
+
+    #[derive(Extractable)]
+    struct Position {
+        x: i32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(position, on_destroy = despawn)]
+    struct Ordered {
+        id: u32,
+        position: Position,
+    }
+
+    registry::on_destroy::<Ordered>(|entity| {
+        EVENTS
+            .lock()
+            .unwrap()
+            .push(format!("registry {}", entity.id));
+    });
+    // Hooks are per concrete type, so this never runs for an Ordered entity.
+    registry::on_destroy::<Position>(|_| {
+        EVENTS.lock().unwrap().push("position type".to_string());
+    });
+
+    let entity = Acquirable::new(Ordered {
+        id: 7,
+        position: Position { x: 3 },
+    });
+    entity.on_destroy(|entity| {
+        EVENTS
+            .lock()
+            .unwrap()
+            .push(format!("instance {}", entity.id));
+    });
+    let position = entity.extract::<Position>().unwrap();
+    position.on_destroy(|position| {
+        EVENTS
+            .lock()
+            .unwrap()
+            .push(format!("position {}", position.x));
+    });
+
+    drop(entity);
+    assert!(EVENTS.lock().unwrap().is_empty());
+    drop(position);
+
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        ["instance 7", "position 3", "registry 7", "derive"]
+    );
+}
+
+/// Test the entity is still dropped when a destroy hook panics
+#[test]
+fn test_entity_data_on_destroy_panic() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Extractable)]
+    struct Fragile {
+        id: u32,
+    }
+
+    impl Drop for Fragile {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let entity = Acquirable::new(Fragile { id: 1 });
+    entity.on_destroy(|_| panic!("hook failed"));
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(entity)));
+    assert!(result.is_err());
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+/// Test instance hooks are skipped for payloads replaced by an earlier hook
+#[test]
+fn test_entity_data_on_destroy_replaced_variant() {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    #[derive(Extractable)]
+    struct Payload {
+        s: String,
+    }
+
+    #[derive(Extractable)]
+    enum State {
+        A(#[extractable] Payload),
+        B(usize, usize, usize),
+    }
+
+    let root = Acquirable::new(State::A(Payload {
+        s: "payload".to_string(),
+    }));
+    let payload = root.extract::<Payload>().unwrap();
+
+    root.on_destroy(|state| *state = State::B(0xdead, 0xbeef, 0x1234));
+    let called = Arc::new(AtomicBool::new(false));
+    let hook = called.clone();
+    payload.on_destroy(move |payload| {
+        hook.store(true, Ordering::SeqCst);
+        assert!(!payload.s.is_empty());
+    });
+
+    drop(payload);
+    drop(root);
+    assert!(!called.load(Ordering::SeqCst));
+}
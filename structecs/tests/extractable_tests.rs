@@ -187,6 +187,98 @@ fn test_extractable_tuple_struct() {
     assert_eq!(extracted.1, "test");
 }
 
+/// Test tuple struct fields listed by index
+#[test]
+fn test_extractable_tuple_struct_indices() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Health {
+        value: u32,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(health)]
+    struct LivingEntity {
+        health: Health,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    struct PlayerData {
+        name: String,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(0, 1)]
+    struct PlayerEntity(LivingEntity, PlayerData);
+
+    let player = Acquirable::new(PlayerEntity(
+        LivingEntity {
+            health: Health { value: 20 },
+        },
+        PlayerData {
+            name: "Steve".to_string(),
+        },
+    ));
+
+    assert_eq!(player.extract::<Health>().unwrap().value, 20);
+    assert!(player.extract::<LivingEntity>().is_some());
+    assert_eq!(player.extract::<PlayerData>().unwrap().name, "Steve");
+}
+
+/// Test field-level attributes on tuple struct fields
+#[test]
+fn test_extractable_tuple_struct_field_attributes() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Marker {
+        id: u8,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Wrapper(#[extractable] Marker, #[extract] u64);
+
+    let wrapper = Acquirable::new(Wrapper(Marker { id: 3 }, 99));
+
+    assert_eq!(wrapper.extract::<Marker>().unwrap().id, 3);
+    assert_eq!(*wrapper.extract::<u64>().unwrap(), 99);
+}
+
+/// Test unit structs as zero-sized marker components
+#[test]
+fn test_extractable_unit_struct_marker() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Flying;
+
+    impl Drop for Flying {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(flying)]
+    struct Bird {
+        id: u32,
+        flying: Flying,
+    }
+
+    let marker = Acquirable::new(Flying);
+    assert!(marker.extract::<Flying>().is_some());
+    drop(marker);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+    let bird = Acquirable::new(Bird {
+        id: 1,
+        flying: Flying,
+    });
+    assert!(bird.extract::<Flying>().is_some());
+    assert_eq!(bird.extract::<Bird>().unwrap().id, 1);
+    drop(bird);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+}
+
 /// Test extraction with references remains valid
 #[test]
 fn test_extraction_reference_lifetime() {
@@ -311,3 +403,599 @@ fn test_clone_with_extraction() {
     // They should point to the same underlying data
     assert!(entity.ptr_eq(&cloned));
 }
+
+/// Test deriving Extractable for a generic struct
+#[test]
+fn test_extractable_generic_struct() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Inner {
+        value: i32,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(inner)]
+    struct Tagged<T> {
+        tag: T,
+        inner: Inner,
+    }
+
+    let tagged = Acquirable::new(Tagged {
+        tag: "first".to_string(),
+        inner: Inner { value: 7 },
+    });
+    assert_eq!(tagged.tag, "first");
+    assert_eq!(tagged.extract::<Inner>().unwrap().value, 7);
+
+    // Each instantiation gets its own extractor
+    let other = Acquirable::new(Tagged {
+        tag: 42u64,
+        inner: Inner { value: 8 },
+    });
+    assert_eq!(other.extract::<Inner>().unwrap().value, 8);
+    assert!(other.extract::<Tagged<u64>>().is_some());
+    assert!(other.extract::<Tagged<String>>().is_none());
+    assert!(tagged.extract::<Tagged<u64>>().is_none());
+}
+
+/// Test deriving Extractable for a const-generic struct nested in another struct
+#[test]
+fn test_extractable_const_generic_struct() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Stack<const N: usize> {
+        items: [u32; N],
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(stack)]
+    struct Holder {
+        id: u32,
+        stack: Stack<3>,
+    }
+
+    let holder = Acquirable::new(Holder {
+        id: 1,
+        stack: Stack { items: [1, 2, 3] },
+    });
+
+    let stack = holder.extract::<Stack<3>>().unwrap();
+    assert_eq!(stack.items, [1, 2, 3]);
+    assert!(holder.extract::<Stack<4>>().is_none());
+}
+
+/// Test distinct instantiations of a generic type are not rejected as duplicates
+#[test]
+fn test_extractable_generic_instantiations_not_duplicates() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Tagged<T> {
+        #[extract]
+        tag: T,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(a, b)]
+    struct Holder {
+        a: Tagged<u32>,
+        b: Tagged<String>,
+    }
+
+    let holder = Acquirable::new(Holder {
+        a: Tagged { tag: 7 },
+        b: Tagged {
+            tag: "seven".to_string(),
+        },
+    });
+    assert_eq!(holder.extract::<Tagged<u32>>().unwrap().tag, 7);
+    assert_eq!(holder.extract::<Tagged<String>>().unwrap().tag, "seven");
+    assert_eq!(*holder.extract::<u32>().unwrap(), 7);
+    assert_eq!(*holder.extract::<String>().unwrap(), "seven");
+}
+
+/// Test the same instantiation of a generic type twice is still rejected when used
+#[test]
+#[should_panic(expected = "contains the same component type at several offsets")]
+fn test_extractable_generic_instantiations_duplicates() {
+    #[derive(Extractable)]
+    struct Tagged<T> {
+        tag: T,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(a, b)]
+    struct Holder {
+        a: Tagged<u32>,
+        b: Tagged<u32>,
+    }
+
+    let _ = Acquirable::new(Holder {
+        a: Tagged { tag: 1 },
+        b: Tagged { tag: 2 },
+    });
+}
+
+/// Test extracting plain fields marked with #[extract]
+#[test]
+fn test_extract_plain_fields() {
+    #[derive(Debug, PartialEq)]
+    struct Vec3 {
+        x: f32,
+        y: f32,
+        z: f32,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum GameMode {
+        Survival,
+        Creative,
+    }
+
+    #[derive(Extractable)]
+    struct Entity {
+        id: u32,
+        #[extract]
+        pos: Vec3,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(entity)]
+    struct Player {
+        entity: Entity,
+        name: String,
+        #[extract]
+        mode: GameMode,
+    }
+
+    let player = Acquirable::new(Player {
+        entity: Entity {
+            id: 1,
+            pos: Vec3 {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        },
+        name: "Steve".to_string(),
+        mode: GameMode::Creative,
+    });
+
+    // Direct and nested plain fields
+    let mode = player.extract::<GameMode>().unwrap();
+    assert_eq!(*mode, GameMode::Creative);
+    let pos = player.extract::<Vec3>().unwrap();
+    assert_eq!(
+        *pos,
+        Vec3 {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0
+        }
+    );
+
+    // Extraction works from an extracted plain field back to the root
+    assert_eq!(pos.extract::<Player>().unwrap().name, "Steve");
+    assert_eq!(pos.extract::<Entity>().unwrap().id, 1);
+
+    // Unmarked fields are not extractable
+    assert!(player.extract::<String>().is_none());
+    assert!(player.extract::<u32>().is_none());
+
+    // Weak references to plain fields
+    let weak = mode.downgrade();
+    drop(mode);
+    drop(pos);
+    assert_eq!(*weak.upgrade().unwrap(), GameMode::Creative);
+    drop(player);
+    assert!(weak.upgrade().is_none());
+}
+
+/// Test field-level extraction attributes
+#[test]
+fn test_field_level_attributes() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Entity {
+        id: u32,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(entity)]
+    struct Health {
+        entity: Entity,
+        value: u32,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Inventory {
+        slots: u8,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Player {
+        #[extractable(flatten)]
+        health: Health,
+        #[extractable]
+        inventory: Inventory,
+        #[extractable(skip)]
+        spawn: Entity,
+        name: String,
+    }
+
+    let player = Acquirable::new(Player {
+        health: Health {
+            entity: Entity { id: 1 },
+            value: 20,
+        },
+        inventory: Inventory { slots: 36 },
+        spawn: Entity { id: 2 },
+        name: "Steve".to_string(),
+    });
+
+    // flatten registers the field and everything nested in it
+    assert_eq!(player.extract::<Health>().unwrap().value, 20);
+    assert_eq!(player.extract::<Entity>().unwrap().id, 1);
+
+    // plain #[extractable] registers only the field's own type
+    assert_eq!(player.extract::<Inventory>().unwrap().slots, 36);
+}
+
+/// Test field-level and struct-level attributes can be mixed
+#[test]
+fn test_field_level_mixed_with_struct_level() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Inner {
+        value: i32,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(inner)]
+    struct Middle {
+        inner: Inner,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Other {
+        flag: bool,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(middle)]
+    struct Outer {
+        middle: Middle,
+        #[extractable]
+        other: Other,
+    }
+
+    let outer = Acquirable::new(Outer {
+        middle: Middle {
+            inner: Inner { value: 5 },
+        },
+        other: Other { flag: true },
+    });
+
+    assert_eq!(outer.extract::<Inner>().unwrap().value, 5);
+    assert!(outer.extract::<Middle>().is_some());
+    assert!(outer.extract::<Other>().unwrap().flag);
+}
+
+/// Test plain #[extractable] does not expose the nested hierarchy
+#[test]
+fn test_field_level_leaf_is_not_flattened() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Inner {
+        value: i32,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(inner)]
+    struct Middle {
+        inner: Inner,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Outer {
+        #[extractable]
+        middle: Middle,
+    }
+
+    let outer = Acquirable::new(Outer {
+        middle: Middle {
+            inner: Inner { value: 5 },
+        },
+    });
+
+    let middle = outer.extract::<Middle>().unwrap();
+    assert_eq!(middle.inner.value, 5);
+    assert!(outer.extract::<Inner>().is_none());
+}
+
+#[derive(Extractable, PartialEq, Debug)]
+struct Hardness {
+    value: u32,
+}
+
+#[derive(Extractable, PartialEq, Debug)]
+#[extractable(hardness)]
+struct Stone {
+    hardness: Hardness,
+}
+
+#[derive(Extractable, PartialEq, Debug)]
+struct Water {
+    level: u8,
+}
+
+#[derive(Extractable, PartialEq, Debug)]
+enum Block {
+    Air,
+    Stone(#[extractable(flatten)] Stone),
+    Water {
+        #[extractable]
+        water: Water,
+        #[extract]
+        flowing: bool,
+    },
+}
+
+/// Test enum variant payloads are extractable only while the variant is held
+#[test]
+fn test_enum_variant_extraction() {
+    let stone = Acquirable::new(Block::Stone(Stone {
+        hardness: Hardness { value: 3 },
+    }));
+    assert_eq!(stone.extract::<Hardness>().unwrap().value, 3);
+    assert!(stone.extract::<Stone>().is_some());
+    assert!(stone.extract::<Water>().is_none());
+    assert!(stone.extract::<bool>().is_none());
+
+    let water = Acquirable::new(Block::Water {
+        water: Water { level: 7 },
+        flowing: true,
+    });
+    assert_eq!(water.extract::<Water>().unwrap().level, 7);
+    assert!(*water.extract::<bool>().unwrap());
+    assert!(water.extract::<Stone>().is_none());
+
+    let air = Acquirable::new(Block::Air);
+    assert!(air.extract::<Block>().is_some());
+    assert!(air.extract::<Stone>().is_none());
+}
+
+/// Test enums nested in a struct are looked up relative to the struct
+#[test]
+fn test_enum_nested_in_struct() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Position {
+        x: i32,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(position, block)]
+    struct PlacedBlock {
+        position: Position,
+        block: Block,
+    }
+
+    let placed = Acquirable::new(PlacedBlock {
+        position: Position { x: 4 },
+        block: Block::Stone(Stone {
+            hardness: Hardness { value: 9 },
+        }),
+    });
+
+    assert_eq!(placed.extract::<Position>().unwrap().x, 4);
+    assert!(placed.extract::<Block>().is_some());
+    assert_eq!(placed.extract::<Hardness>().unwrap().value, 9);
+    assert!(placed.extract::<Water>().is_none());
+
+    let hardness = placed.extract::<Hardness>().unwrap();
+    assert_eq!(hardness.downgrade().upgrade().unwrap().value, 9);
+}
+
+/// Test generic enums and extract_all across variant fields
+#[test]
+fn test_enum_generic_extract_all() {
+    #[derive(Extractable, PartialEq, Debug)]
+    enum Pair<T> {
+        Both(#[extract] T, #[extract] T),
+        One(#[extract] T),
+    }
+
+    let both = Acquirable::new(Pair::Both(1u32, 2u32));
+    let values: Vec<u32> = both.extract_all::<u32>().iter().map(|v| **v).collect();
+    assert_eq!(values, [1, 2]);
+    assert_eq!(*both.extract::<u32>().unwrap(), 1);
+
+    let one = Acquirable::new(Pair::One(5u32));
+    assert_eq!(one.extract_all::<u32>().len(), 1);
+}
+
+/// Test parent walks up to the struct holding the component
+#[test]
+fn test_parent_nearest_container() {
+    #[derive(Extractable)]
+    struct Enchantment {
+        level: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(enchantment)]
+    struct Item {
+        name: &'static str,
+        enchantment: Enchantment,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(main_hand, off_hand, allow_duplicates = true)]
+    struct Player {
+        main_hand: Item,
+        off_hand: Item,
+    }
+
+    let player = Acquirable::new(Player {
+        main_hand: Item {
+            name: "sword",
+            enchantment: Enchantment { level: 1 },
+        },
+        off_hand: Item {
+            name: "shield",
+            enchantment: Enchantment { level: 2 },
+        },
+    });
+    let enchantments = player.extract_all::<Enchantment>();
+    assert_eq!(enchantments[1].level, 2);
+
+    // `extract` resolves from the root and finds the first Item
+    assert_eq!(enchantments[1].extract::<Item>().unwrap().name, "sword");
+    assert_eq!(enchantments[1].parent::<Item>().unwrap().name, "shield");
+    assert_eq!(enchantments[0].parent::<Item>().unwrap().name, "sword");
+
+    assert!(enchantments[1].parent::<Player>().unwrap().ptr_eq(&player));
+    assert!(enchantments[1].parent::<Enchantment>().is_none());
+    assert!(player.parent::<Player>().is_none());
+    assert!(
+        player
+            .extract::<Item>()
+            .unwrap()
+            .parent::<Enchantment>()
+            .is_none()
+    );
+}
+
+/// Test path reports field names from the concrete type
+#[test]
+fn test_path_field_names() {
+    #[derive(Extractable)]
+    struct Health {
+        value: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(health)]
+    struct LivingEntity {
+        id: u32,
+        health: Health,
+    }
+
+    struct Vec3(f32, f32, f32);
+
+    #[derive(Extractable)]
+    #[extractable(0, allow_duplicates = true)]
+    struct Player(LivingEntity, #[extract] Vec3, #[extractable] Health);
+
+    let player = Acquirable::new(Player(
+        LivingEntity {
+            id: 1,
+            health: Health { value: 20 },
+        },
+        Vec3(0.0, 64.0, 0.0),
+        Health { value: 5 },
+    ));
+
+    assert!(player.path().is_empty());
+    assert_eq!(player.extract::<LivingEntity>().unwrap().path(), ["0"]);
+    assert_eq!(player.extract::<Vec3>().unwrap().path(), ["1"]);
+
+    let health: Vec<_> = player
+        .extract_all::<Health>()
+        .iter()
+        .map(|health| health.path())
+        .collect();
+    assert_eq!(health, [vec!["0", "health"], vec!["2"]]);
+}
+
+/// Test path and parent through enum variants
+#[test]
+fn test_path_enum_variant() {
+    #[derive(Extractable)]
+    struct Hardness {
+        value: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(hardness)]
+    struct Stone {
+        hardness: Hardness,
+    }
+
+    #[derive(Extractable)]
+    enum Block {
+        Air,
+        Stone(#[extractable(flatten)] Stone),
+        Ore {
+            #[extractable(flatten)]
+            stone: Stone,
+        },
+    }
+
+    #[derive(Extractable)]
+    #[extractable(block)]
+    struct Placed {
+        block: Block,
+    }
+
+    let placed = Acquirable::new(Placed {
+        block: Block::Ore {
+            stone: Stone {
+                hardness: Hardness { value: 3 },
+            },
+        },
+    });
+    let hardness = placed.extract::<Hardness>().unwrap();
+    assert_eq!(hardness.path(), ["block", "Ore.stone", "hardness"]);
+    assert!(hardness.parent::<Block>().is_some());
+    assert!(hardness.parent::<Placed>().unwrap().ptr_eq(&placed));
+
+    let stone = Acquirable::new(Block::Stone(Stone {
+        hardness: Hardness { value: 1 },
+    }));
+    assert_eq!(
+        stone.extract::<Hardness>().unwrap().path(),
+        ["Stone.0", "hardness"]
+    );
+}
+
+/// Test the checked APIs in every build profile
+#[test]
+fn test_checked_extraction() {
+    #[derive(Extractable)]
+    struct Entity {
+        id: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(entity)]
+    struct Player {
+        name: String,
+        entity: Entity,
+    }
+
+    let entity: Acquirable<Entity> = Acquirable::new_checked(Player {
+        name: "Alice".to_string(),
+        entity: Entity { id: 1 },
+    });
+    assert_eq!(entity.id, 1);
+
+    let player = entity.extract::<Player>().unwrap();
+    assert!(player.extract_checked::<Entity>().ptr_eq(&entity));
+    assert_eq!(player.extract_checked::<Player>().name, "Alice");
+}
+
+/// Test the checked APIs panic for another instantiation of a generic type, which the
+/// compile-time check cannot tell apart
+#[test]
+#[should_panic(expected = "as an extractable component")]
+fn test_checked_generic_instantiation_mismatch() {
+    #[derive(Extractable)]
+    struct Tagged<T> {
+        value: T,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(tagged)]
+    struct Holder {
+        tagged: Tagged<u32>,
+    }
+
+    let holder = Acquirable::new(Holder {
+        tagged: Tagged { value: 1 },
+    });
+    let _ = holder.extract_checked::<Tagged<u64>>();
+}
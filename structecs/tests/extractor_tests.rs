@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use std::any::TypeId;
+
 use structecs::*;
 
 /// Test basic offset calculation with simple struct
@@ -374,3 +376,232 @@ fn test_extractor_vec_fields() {
     assert_eq!(extracted.id, 1);
     assert_eq!(extracted.items, vec![10, 20, 30, 40, 50]);
 }
+
+/// Hand-written impl that opts into lazily built extractors
+struct ManualLazy {
+    value: u32,
+}
+
+impl Extractable for ManualLazy {
+    const METADATA_LIST: &'static [ExtractionMetadata] = &[ExtractionMetadata::new::<Self>(0)];
+    const IDENTIFIER: &'static str = "extractor_tests::ManualLazy";
+    const REGISTERED: bool = false;
+}
+
+/// Hand-written impl that claims registration but never submits to the inventory
+struct ManualUnregistered {
+    value: u32,
+}
+
+impl Extractable for ManualUnregistered {
+    const METADATA_LIST: &'static [ExtractionMetadata] = &[ExtractionMetadata::new::<Self>(0)];
+    const IDENTIFIER: &'static str = "extractor_tests::ManualUnregistered";
+}
+
+/// Test the extractor is built on demand for types outside the inventory
+#[test]
+fn test_extractor_built_on_demand() {
+    let first = Acquirable::new(ManualLazy { value: 1 });
+    let second = Acquirable::new(ManualLazy { value: 2 });
+
+    assert_eq!(first.extract::<ManualLazy>().unwrap().value, 1);
+    assert_eq!(second.extract::<ManualLazy>().unwrap().value, 2);
+}
+
+/// Test a missing inventory registration is reported with the type name in debug builds
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "ManualUnregistered` has no inventory registration")]
+fn test_extractor_missing_registration_panics() {
+    let _ = Acquirable::new(ManualUnregistered { value: 1 });
+}
+
+/// Test a missing inventory registration falls back to an on-demand extractor in release builds
+#[cfg(not(debug_assertions))]
+#[test]
+fn test_extractor_missing_registration_fallback() {
+    let entity = Acquirable::new(ManualUnregistered { value: 1 });
+    assert_eq!(entity.extract::<ManualUnregistered>().unwrap().value, 1);
+}
+
+/// Test duplicated component types are all reachable when allowed
+#[test]
+fn test_extractor_allowed_duplicates() {
+    #[derive(Extractable, PartialEq, Debug)]
+    struct Item {
+        id: u32,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(item)]
+    struct Slot {
+        index: u8,
+        item: Item,
+    }
+
+    #[derive(Extractable, PartialEq, Debug)]
+    #[extractable(main_hand, off_hand, backpack, allow_duplicates = true)]
+    struct Player {
+        main_hand: Item,
+        off_hand: Item,
+        backpack: Slot,
+    }
+
+    let player = Acquirable::new(Player {
+        main_hand: Item { id: 1 },
+        off_hand: Item { id: 2 },
+        backpack: Slot {
+            index: 0,
+            item: Item { id: 3 },
+        },
+    });
+
+    // extract returns the first occurrence in declaration order
+    assert_eq!(player.extract::<Item>().unwrap().id, 1);
+
+    let items: Vec<u32> = player
+        .extract_all::<Item>()
+        .iter()
+        .map(|item| item.id)
+        .collect();
+    assert_eq!(items, [1, 2, 3]);
+
+    // Non-duplicated types yield a single element, missing types none
+    assert_eq!(player.extract_all::<Slot>().len(), 1);
+    assert_eq!(player.extract_all::<Player>().len(), 1);
+    assert!(player.extract_all::<String>().is_empty());
+}
+
+mod math {
+    pub struct Vec3(pub f32, pub f32, pub f32);
+}
+
+type Pos = math::Vec3;
+
+// Differently spelled paths are not caught by the identifier-based const check. The
+// struct is registered in the global cache shared by every test in this file.
+#[derive(Extractable)]
+struct DuplicatedEntity {
+    #[extract]
+    pos: math::Vec3,
+    #[extract]
+    velocity: Pos,
+}
+
+/// Test duplicates missed by the const check are caught when the type is used
+#[test]
+#[should_panic(
+    expected = "contains the same component type at several offsets (`pos`, `velocity`)"
+)]
+fn test_extractor_runtime_duplicate_detection() {
+    let _ = Acquirable::new(DuplicatedEntity {
+        pos: math::Vec3(0.0, 0.0, 0.0),
+        velocity: math::Vec3(0.0, 0.0, 0.0),
+    });
+}
+
+/// Test duplicates in a generic type are caught when the instantiation is used
+#[test]
+#[should_panic(
+    expected = "contains the same component type at several offsets (`pos`, `velocity`)"
+)]
+fn test_extractor_runtime_duplicate_detection_generic() {
+    #[derive(Extractable)]
+    struct Entity<T> {
+        #[extract]
+        pos: math::Vec3,
+        #[extract]
+        velocity: Pos,
+        tag: T,
+    }
+
+    let _ = Acquirable::new(Entity {
+        pos: math::Vec3(0.0, 0.0, 0.0),
+        velocity: math::Vec3(0.0, 0.0, 0.0),
+        tag: (),
+    });
+}
+
+/// Test a type with duplicates does not affect the other registered types
+#[test]
+fn test_extractor_runtime_duplicate_isolated() {
+    #[derive(Extractable)]
+    struct Other {
+        id: u32,
+    }
+
+    let other = Acquirable::new(Other { id: 7 });
+    assert_eq!(other.extract::<Other>().unwrap().id, 7);
+    assert!(registry::find_type(TypeId::of::<Other>()).is_some());
+    assert!(registry::find_type(TypeId::of::<DuplicatedEntity>()).is_none());
+    assert!(
+        registry::registered_types()
+            .iter()
+            .all(|extractor| extractor.type_id() != TypeId::of::<DuplicatedEntity>())
+    );
+}
+
+/// Test field paths of the components of a registered type
+#[test]
+fn test_extractor_field_paths() {
+    #[derive(Extractable)]
+    struct Health {
+        value: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(health)]
+    struct LivingEntity {
+        health: Health,
+    }
+
+    #[derive(Extractable)]
+    enum Mount {
+        None,
+        Horse(#[extractable(flatten)] LivingEntity),
+    }
+
+    #[derive(Extractable)]
+    #[extractable(living, mount)]
+    struct Player {
+        living: LivingEntity,
+        mount: Mount,
+    }
+
+    let player = registry::find_type(TypeId::of::<Player>()).unwrap();
+
+    assert_eq!(
+        player.field_path(TypeId::of::<Health>()).unwrap().join("."),
+        "living.health"
+    );
+    assert_eq!(player.field_path(TypeId::of::<Player>()).unwrap().len(), 0);
+    assert!(player.field_path(TypeId::of::<String>()).is_none());
+
+    assert_eq!(
+        player.field_paths(TypeId::of::<Health>()),
+        [vec!["living", "health"], vec!["mount", "Horse.0", "health"]]
+    );
+    assert_eq!(player.field_paths(TypeId::of::<Mount>()), [vec!["mount"]]);
+}
+
+/// Test metadata entries carry their field and type names
+#[test]
+fn test_metadata_field_and_type_names() {
+    #[derive(Extractable)]
+    struct Health {
+        value: u32,
+    }
+
+    #[derive(Extractable)]
+    #[extractable(health)]
+    struct LivingEntity {
+        health: Health,
+    }
+
+    let list = LivingEntity::METADATA_LIST;
+    assert_eq!(list[0].field(), "");
+    assert!(list[0].type_name().ends_with("LivingEntity"));
+    assert_eq!(list[1].field(), "health");
+    assert!(list[1].type_name().ends_with("Health"));
+    assert_eq!(list[1].type_id(), TypeId::of::<Health>());
+}
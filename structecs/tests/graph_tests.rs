@@ -0,0 +1,251 @@
+#![cfg(all(feature = "serde", feature = "archetype"))]
+#![allow(dead_code)]
+
+use serde::{Deserialize, Deserializer, Serialize};
+use structecs::{graph::Graph, *};
+
+#[derive(Extractable, Serialize, Deserialize, Debug)]
+#[extractable(tag = "entity")]
+struct Entity {
+    id: u32,
+}
+
+#[derive(Extractable, Serialize, Deserialize, Debug)]
+#[extractable(entity, tag = "player")]
+struct Player {
+    entity: Entity,
+    name: String,
+}
+
+#[derive(Extractable, Serialize, Deserialize)]
+#[extractable(entity, tag = "zombie")]
+struct Zombie {
+    entity: Entity,
+    target: WeakAcquirable<Player>,
+}
+
+#[derive(Extractable, Serialize, Deserialize, Debug)]
+#[extractable(entity, tag = "boat")]
+struct Boat {
+    entity: Entity,
+    passengers: Vec<Acquirable<Player>>,
+}
+
+fn player(id: u32, name: &str) -> Acquirable<Player> {
+    Acquirable::new(Player {
+        entity: Entity { id },
+        name: name.to_string(),
+    })
+}
+
+fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> serde_json::Result<T> {
+    let json = serde_json::to_string(&Graph(value))?;
+    let Graph(loaded) = serde_json::from_str(&json)?;
+    Ok(loaded)
+}
+
+/// Test shared strong references are written once and shared again on load
+#[test]
+fn test_shared_strong_references() {
+    let alice = player(1, "Alice");
+    let boat = Acquirable::new(Boat {
+        entity: Entity { id: 2 },
+        passengers: vec![alice.clone()],
+    });
+    let world = (vec![alice.clone(), alice], boat);
+
+    let json = serde_json::to_string(&Graph(&world)).unwrap();
+    assert_eq!(json.matches("Alice").count(), 1);
+
+    let Graph((players, boat)): Graph<(Vec<Acquirable<Player>>, Acquirable<Boat>)> =
+        serde_json::from_str(&json).unwrap();
+    assert!(players[0].ptr_eq(&players[1]));
+    assert!(players[0].ptr_eq(&boat.passengers[0]));
+    assert_eq!(players[0].name, "Alice");
+}
+
+/// Test dependencies are written before the entities referencing them
+#[test]
+fn test_serialized_format() {
+    let boat = Acquirable::new(Boat {
+        entity: Entity { id: 2 },
+        passengers: vec![player(1, "Alice")],
+    });
+
+    let json = serde_json::to_string(&Graph(&boat)).unwrap();
+    assert_eq!(
+        json,
+        r#"{"types":["player","boat"],"entities":[{"entity":{"id":1},"name":"Alice"},{"entity":{"id":2},"passengers":[0]}],"root":1}"#
+    );
+}
+
+/// Test weak references to entities written later are rebuilt
+#[test]
+fn test_forward_weak_reference() {
+    let alice = player(1, "Alice");
+    let zombie = Acquirable::new(Zombie {
+        entity: Entity { id: 2 },
+        target: alice.downgrade(),
+    });
+    let world = (zombie, alice);
+
+    let (zombie, alice): (Acquirable<Zombie>, Acquirable<Player>) = round_trip(&world).unwrap();
+    let target = zombie.target.upgrade().unwrap();
+    assert!(target.ptr_eq(&alice));
+    assert_eq!(target.name, "Alice");
+}
+
+/// Test loaded entities get new IDs, also seen by forward weak references
+#[cfg(feature = "entity-id")]
+#[test]
+fn test_entity_ids_after_load() {
+    let alice = player(1, "Alice");
+    let zombie = Acquirable::new(Zombie {
+        entity: Entity { id: 2 },
+        target: alice.downgrade(),
+    });
+    let world = (zombie, alice);
+
+    let (zombie, alice): (Acquirable<Zombie>, Acquirable<Player>) = round_trip(&world).unwrap();
+    assert_ne!(alice.entity_id(), world.1.entity_id());
+    assert_ne!(zombie.entity_id(), world.0.entity_id());
+    assert_eq!(zombie.target.entity_id(), Some(alice.entity_id()));
+}
+
+/// Test weak references to entities outside the graph never upgrade
+#[test]
+fn test_weak_reference_outside_graph() {
+    let alice = player(1, "Alice");
+    let zombie = Acquirable::new(Zombie {
+        entity: Entity { id: 2 },
+        target: alice.downgrade(),
+    });
+
+    let loaded: Acquirable<Zombie> = round_trip(&zombie).unwrap();
+    assert!(loaded.target.upgrade().is_none());
+    assert!(zombie.target.upgrade().is_some());
+}
+
+/// Test weak references to dropped entities never upgrade
+#[test]
+fn test_weak_reference_to_dropped_entity() {
+    let zombie = Acquirable::new(Zombie {
+        entity: Entity { id: 2 },
+        target: player(1, "Alice").downgrade(),
+    });
+
+    let loaded: Acquirable<Zombie> = round_trip(&zombie).unwrap();
+    assert!(loaded.target.upgrade().is_none());
+}
+
+/// Test an archetype in a graph keeps sharing with other references
+#[test]
+fn test_archetype_in_graph() {
+    let archetype: Archetype<u32, Entity> = Archetype::default();
+    let alice = archetype.insert(
+        1,
+        Player {
+            entity: Entity { id: 1 },
+            name: "Alice".to_string(),
+        },
+    );
+    let boat = Acquirable::new(Boat {
+        entity: Entity { id: 2 },
+        passengers: vec![alice],
+    });
+    archetype
+        .write()
+        .insert(2, boat.extract::<Entity>().unwrap());
+
+    let loaded: Archetype<u32, Entity> = round_trip(&archetype).unwrap();
+    let boat = loaded.get(&2).unwrap().extract::<Boat>().unwrap();
+    let alice = loaded.get(&1).unwrap();
+    assert!(alice.is::<Player>());
+    assert!(boat.passengers[0].ptr_eq(&alice));
+}
+
+/// Test weak references cannot be upgraded before their entity is deserialized
+#[test]
+fn test_weak_reference_not_upgradable_while_loading() {
+    #[derive(Serialize, Deserialize)]
+    struct Checked(#[serde(deserialize_with = "not_yet_upgradable")] WeakAcquirable<Player>);
+
+    fn not_yet_upgradable<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<WeakAcquirable<Player>, D::Error> {
+        let weak = WeakAcquirable::<Player>::deserialize(deserializer)?;
+        assert!(weak.upgrade().is_none());
+        Ok(weak)
+    }
+
+    #[derive(Extractable, Serialize, Deserialize)]
+    #[extractable(tag = "checked_holder")]
+    struct Holder {
+        checked: Checked,
+    }
+
+    let alice = player(1, "Alice");
+    let holder = Acquirable::new(Holder {
+        checked: Checked(alice.downgrade()),
+    });
+    let world = (holder, alice);
+
+    let (holder, alice): (Acquirable<Holder>, Acquirable<Player>) = round_trip(&world).unwrap();
+    assert!(holder.checked.0.upgrade().unwrap().ptr_eq(&alice));
+}
+
+/// Test a strong reference to an entity before its definition is rejected
+#[test]
+fn test_strong_reference_before_definition_fails() {
+    let json = r#"{"types":["boat","player"],"entities":[{"entity":{"id":2},"passengers":[1]},{"entity":{"id":1},"name":"Alice"}],"root":0}"#;
+
+    let error = serde_json::from_str::<Graph<Acquirable<Boat>>>(json)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("before its definition"));
+}
+
+/// Test weak references cannot be serialized outside a graph
+#[test]
+fn test_weak_reference_outside_graph_fails() {
+    let alice = player(1, "Alice");
+    let zombie = Acquirable::new(Zombie {
+        entity: Entity { id: 2 },
+        target: alice.downgrade(),
+    });
+
+    let error = serde_json::to_string(&zombie).unwrap_err();
+    assert!(error.to_string().contains("only be serialized inside"));
+}
+
+/// Test serializing a graph of entities the current thread reads does not wait for a
+/// queued writer
+#[cfg(feature = "locking")]
+#[test]
+fn test_serialize_while_read_locked() {
+    let alice = Acquirable::new_locked(Player {
+        entity: Entity { id: 1 },
+        name: "Alice".to_string(),
+    });
+    let boat = Acquirable::new_locked(Boat {
+        entity: Entity { id: 2 },
+        passengers: vec![alice.clone()],
+    });
+    let guards = (alice.read(), boat.read());
+    let writers = [alice.extract::<Entity>(), boat.extract::<Entity>()]
+        .into_iter()
+        .flatten()
+        .map(|entity| std::thread::spawn(move || entity.write().id += 10))
+        .collect::<Vec<_>>();
+    // Give the writers time to queue behind the read guards.
+    std::thread::sleep(std::time::Duration::from_millis(50));
+
+    let json = serde_json::to_string(&Graph(&boat)).unwrap();
+    assert_eq!(json.matches("Alice").count(), 1);
+
+    drop(guards);
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    assert_eq!(boat.read().passengers[0].read().entity.id, 11);
+}
@@ -0,0 +1,192 @@
+#![cfg(feature = "locking")]
+#![allow(dead_code)]
+
+use std::{sync::Arc, thread};
+
+use structecs::*;
+
+#[derive(Extractable, Debug, PartialEq)]
+struct Health {
+    current: u32,
+    max: u32,
+}
+
+#[derive(Extractable, Debug)]
+#[extractable(health)]
+struct Player {
+    name: String,
+    health: Health,
+}
+
+fn new_player_data() -> Player {
+    Player {
+        name: "Steve".to_string(),
+        health: Health {
+            current: 20,
+            max: 20,
+        },
+    }
+}
+
+fn new_player() -> Acquirable<Player> {
+    Acquirable::new_locked(new_player_data())
+}
+
+/// Test writing through an extracted component is visible from the root
+#[test]
+fn test_write_through_extracted_component() {
+    let player = new_player();
+
+    player.extract::<Health>().unwrap().write().current -= 5;
+
+    assert_eq!(player.read().health.current, 15);
+    assert_eq!(player.extract::<Health>().unwrap().read().current, 15);
+}
+
+/// Test the lock is shared by every component of the same entity
+#[test]
+fn test_lock_is_per_entity() {
+    let player = new_player();
+    let health = player.extract::<Health>().unwrap();
+
+    let guard = player.write();
+    assert!(health.try_read().is_none());
+    assert!(health.try_write().is_none());
+    drop(guard);
+
+    let read = player.read();
+    assert!(health.try_read().is_some());
+    assert!(health.try_write().is_none());
+    drop(read);
+
+    assert!(health.try_write().is_some());
+}
+
+/// Test separate entities are locked independently
+#[test]
+fn test_lock_does_not_span_entities() {
+    let player1 = new_player();
+    let player2 = new_player();
+
+    let _guard = player1.write();
+    assert!(player2.try_write().is_some());
+}
+
+/// Test concurrent writers through different extracted components
+#[test]
+fn test_concurrent_writes() {
+    let player = new_player();
+    player.write().health.current = 0;
+
+    let player = Arc::new(player);
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let player = Arc::clone(&player);
+            thread::spawn(move || {
+                let health = player.extract::<Health>().unwrap();
+                for _ in 0..1000 {
+                    health.write().current += 1;
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(player.read().health.current, 8000);
+}
+
+/// Test Debug output goes through the read lock
+#[test]
+fn test_debug_format() {
+    let health = new_player().extract::<Health>().unwrap();
+    assert_eq!(
+        format!("{:?}", health),
+        "Acquirable { target: Health { current: 20, max: 20 } }"
+    );
+    assert_eq!(
+        format!("{:?}", health.read()),
+        "Health { current: 20, max: 20 }"
+    );
+}
+
+#[derive(Extractable, Debug)]
+enum Slot {
+    Empty,
+    Filled(#[extractable(flatten)] Player),
+}
+
+/// Test replacing an enum variant invalidates components extracted from it
+#[test]
+#[should_panic(expected = "is no longer present")]
+fn test_replaced_variant_panics() {
+    let slot = Acquirable::new_locked(Slot::Filled(new_player_data()));
+    let health = slot.extract::<Health>().unwrap();
+    assert_eq!(health.read().current, 20);
+
+    *slot.write() = Slot::Empty;
+    assert!(slot.extract::<Health>().is_none());
+
+    let _ = health.read();
+}
+
+/// Test entities created without new_locked keep Deref and can be read
+#[test]
+fn test_unlocked_entity_derefs() {
+    let player = Acquirable::new(new_player_data());
+    let health = player.extract::<Health>().unwrap();
+
+    assert_eq!(health.current, 20);
+    assert_eq!(health.read().current, 20);
+    assert_eq!(player.name, "Steve");
+}
+
+/// Test writing to an entity created without new_locked panics
+#[test]
+#[should_panic(expected = "create it with `Acquirable::new_locked`")]
+fn test_unlocked_entity_write_panics() {
+    let player = Acquirable::new(new_player_data());
+    player.extract::<Health>().unwrap().write().current = 0;
+}
+
+/// Test dereferencing a locked entity panics
+#[test]
+#[should_panic(expected = "access it through `read()` or `write()`")]
+fn test_locked_entity_deref_panics() {
+    let health = new_player().extract::<Health>().unwrap();
+    let _ = health.current;
+}
+
+/// Test entities inserted locked into each archetype backend can be written
+#[cfg(feature = "archetype")]
+#[test]
+fn test_archetype_insert_locked_write() {
+    let archetype = Archetype::<u32, Health>::default();
+    archetype.insert_locked(1, new_player_data());
+    archetype.get(&1).unwrap().write().current = 10;
+    assert_eq!(archetype.get(&1).unwrap().read().current, 10);
+    let (_, player) = archetype.iter_as::<Player>().next().unwrap();
+    assert_eq!(player.read().health.current, 10);
+
+    let sharded = ShardedArchetype::<u32, Health>::default();
+    let player = sharded.insert_locked(1, new_player_data());
+    sharded.get(&1).unwrap().write().current = 11;
+    assert_eq!(player.read().health.current, 11);
+
+    let rcu = RcuArchetype::<u32, Health>::default();
+    let player = rcu.insert_locked(1, new_player_data());
+    rcu.get(&1).unwrap().write().current = 12;
+    assert_eq!(player.read().health.current, 12);
+}
+
+/// Test entities inserted into an archetype without locking cannot be written
+#[cfg(feature = "archetype")]
+#[test]
+#[should_panic(expected = "or `insert_locked` when inserting it into an archetype")]
+fn test_archetype_insert_write_panics() {
+    let archetype = Archetype::<u32, Health>::default();
+    archetype.insert(1, new_player_data());
+    let _ = archetype.get(&1).unwrap().write();
+}
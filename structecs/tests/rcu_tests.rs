@@ -0,0 +1,214 @@
+#![cfg(feature = "archetype")]
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use structecs::*;
+
+#[derive(Extractable, Debug)]
+struct Entity {
+    id: u32,
+}
+
+#[derive(Extractable, Debug)]
+#[extractable(entity)]
+struct Player {
+    name: String,
+    entity: Entity,
+}
+
+/// Test insert, get and remove
+#[test]
+fn test_rcu_insert_get_remove() {
+    let archetype: RcuArchetype<u32, Entity> = RcuArchetype::default();
+    assert!(archetype.is_empty());
+
+    let player = archetype.insert(
+        1,
+        Player {
+            name: "Alice".to_string(),
+            entity: Entity { id: 1 },
+        },
+    );
+    archetype.insert(2, Entity { id: 2 });
+
+    assert_eq!(archetype.len(), 2);
+    assert!(archetype.contains_key(&1));
+    assert!(archetype.get(&1).unwrap().ptr_eq(&player));
+    assert_eq!(archetype.get(&2).unwrap().id, 2);
+
+    assert_eq!(archetype.remove(&2).unwrap().id, 2);
+    assert!(archetype.remove(&2).is_none());
+    assert!(archetype.get(&2).is_none());
+
+    archetype.clear();
+    assert!(archetype.is_empty());
+}
+
+/// Test a read guard keeps its snapshot while the archetype changes
+#[test]
+fn test_rcu_read_guard_snapshot() {
+    let archetype: RcuArchetype<u32, Entity> = RcuArchetype::default();
+    archetype.insert(1, Entity { id: 1 });
+
+    let snapshot = archetype.read();
+    let writer = {
+        let archetype = archetype.clone();
+        std::thread::spawn(move || archetype.insert(2, Entity { id: 2 }))
+    };
+
+    // The writer waits for the guard, which still sees the old snapshot
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert_eq!(snapshot.len(), 1);
+    assert!(!snapshot.contains_key(&2));
+    drop(snapshot);
+
+    writer.join().unwrap();
+    assert_eq!(archetype.len(), 2);
+}
+
+/// Test update publishes all modifications at once
+#[test]
+fn test_rcu_update() {
+    let archetype: RcuArchetype<u32, Entity> = RcuArchetype::default();
+
+    let inserted = archetype.update(|map| {
+        for id in 0..10 {
+            map.insert(id, Acquirable::new(Entity { id }));
+        }
+        map.len()
+    });
+    assert_eq!(inserted, 10);
+
+    archetype.update(|map| {
+        map.insert(
+            10,
+            Acquirable::new(Player {
+                name: "Bob".to_string(),
+                entity: Entity { id: 10 },
+            })
+            .extract::<Entity>()
+            .unwrap(),
+        );
+    });
+
+    let players: Vec<_> = archetype.iter_as::<Player>().collect();
+    assert_eq!(players.len(), 1);
+    assert_eq!(players[0].0, 10);
+    assert_eq!(archetype.iter_as::<Entity>().count(), 11);
+}
+
+/// Test the query helpers
+#[test]
+fn test_rcu_queries() {
+    let archetype: RcuArchetype<u32, Entity> = RcuArchetype::default();
+    for id in 0..10 {
+        archetype.insert(id, Entity { id });
+    }
+
+    let mut sum = 0;
+    archetype.for_each(|_, entity| sum += entity.id);
+    assert_eq!(sum, 45);
+
+    assert_eq!(archetype.filter(|key, _| *key < 3).len(), 3);
+
+    let drained = archetype.drain_filter(|key, _| key % 2 == 0);
+    assert_eq!(drained.len(), 5);
+    assert_eq!(archetype.len(), 5);
+
+    archetype.retain(|key, _| *key < 5);
+    assert_eq!(archetype.len(), 2);
+}
+
+/// Test entities are dropped exactly once, by replacement, removal and drop
+#[test]
+fn test_rcu_drops_entities() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Extractable)]
+    struct Counted {
+        id: u32,
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let archetype: RcuArchetype<u32, Counted> = RcuArchetype::default();
+    for id in 0..4 {
+        archetype.insert(id, Counted { id });
+    }
+    archetype.insert(0, Counted { id: 4 });
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+    drop(archetype.remove(&1));
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+
+    drop(archetype.clone());
+    assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+
+    drop(archetype);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 5);
+}
+
+/// Test readers see consistent entities while writers insert and remove
+#[test]
+fn test_rcu_concurrent_readers_and_writers() {
+    let archetype: RcuArchetype<u32, Entity> = RcuArchetype::default();
+    for id in 0..20 {
+        archetype.insert(id, Entity { id });
+    }
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                let mut reads = 0u32;
+                while !done.load(Ordering::Relaxed) || reads < 1000 {
+                    let id = reads % 40;
+                    if let Some(entity) = archetype.get(&id) {
+                        assert_eq!(entity.id, id);
+                    }
+                    reads += 1;
+                }
+            });
+        }
+        scope.spawn(|| {
+            for id in 20..40 {
+                archetype.insert(id, Entity { id });
+                archetype.remove(&(id - 20));
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+    });
+
+    assert_eq!(archetype.len(), 20);
+    for id in 20..40 {
+        assert_eq!(archetype.get(&id).unwrap().id, id);
+    }
+}
+
+/// Test RCU archetypes round-trip through serde
+#[cfg(feature = "serde")]
+#[test]
+fn test_rcu_serde_round_trip() {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Extractable, Serialize, Deserialize)]
+    #[extractable(tag = "rcu_item")]
+    struct Item {
+        count: u32,
+    }
+
+    let archetype: RcuArchetype<u32, Item> = RcuArchetype::default();
+    for count in 0..10 {
+        archetype.insert(count, Item { count });
+    }
+
+    let json = serde_json::to_string(&archetype).unwrap();
+    let loaded: RcuArchetype<u32, Item> = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.len(), 10);
+    assert_eq!(loaded.get(&3).unwrap().count, 3);
+}
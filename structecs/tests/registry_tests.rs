@@ -0,0 +1,154 @@
+#![allow(dead_code)]
+
+use std::any::TypeId;
+
+use structecs::*;
+
+#[derive(Extractable)]
+struct Entity {
+    id: u32,
+}
+
+#[derive(Extractable)]
+#[extractable(entity)]
+struct LivingEntity {
+    health: u32,
+    entity: Entity,
+}
+
+#[derive(Extractable)]
+#[extractable(living)]
+struct Player {
+    name: String,
+    living: LivingEntity,
+}
+
+#[derive(Extractable)]
+#[extractable(living)]
+struct Zombie {
+    living: LivingEntity,
+}
+
+#[derive(Extractable)]
+#[extractable(entity)]
+struct Item {
+    entity: Entity,
+    count: u8,
+}
+
+#[derive(Extractable)]
+struct Wrapper<T> {
+    value: T,
+}
+
+/// Test registered types expose their name and layout
+#[test]
+fn test_registry_type_info() {
+    let player = registry::find_type(TypeId::of::<Player>()).unwrap();
+
+    assert_eq!(player.type_id(), TypeId::of::<Player>());
+    assert_eq!(player.type_name(), std::any::type_name::<Player>());
+    assert_eq!(player.size(), size_of::<Player>());
+    assert_eq!(player.align(), align_of::<Player>());
+}
+
+/// Test every derived type is listed
+#[test]
+fn test_registry_lists_all_types() {
+    let types: Vec<TypeId> = registry::registered_types()
+        .iter()
+        .map(|extractor| extractor.type_id())
+        .collect();
+
+    for type_id in [
+        TypeId::of::<Entity>(),
+        TypeId::of::<LivingEntity>(),
+        TypeId::of::<Player>(),
+        TypeId::of::<Zombie>(),
+        TypeId::of::<Item>(),
+    ] {
+        assert!(types.contains(&type_id));
+    }
+}
+
+/// Test components are reported with their names and offsets
+#[test]
+fn test_registry_components() {
+    let player = registry::find_type(TypeId::of::<Player>()).unwrap();
+
+    let mut names: Vec<&str> = player.components().map(|(_, name, _)| name).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            std::any::type_name::<Entity>(),
+            std::any::type_name::<LivingEntity>(),
+            std::any::type_name::<Player>(),
+        ]
+    );
+    for (type_id, _, offset) in player.components() {
+        assert_eq!(player.offset_of(type_id), Some(offset));
+    }
+
+    let living_offset = std::mem::offset_of!(Player, living);
+    let entity_offset = living_offset + std::mem::offset_of!(LivingEntity, entity);
+    assert_eq!(player.offset_of(TypeId::of::<Player>()), Some(0));
+    assert_eq!(
+        player.offset_of(TypeId::of::<LivingEntity>()),
+        Some(living_offset)
+    );
+    assert_eq!(
+        player.offset_of(TypeId::of::<Entity>()),
+        Some(entity_offset)
+    );
+    assert_eq!(player.offset_of(TypeId::of::<Item>()), None);
+}
+
+/// Test reverse lookup of types containing a base
+#[test]
+fn test_registry_types_containing() {
+    let mut living: Vec<&str> = registry::types_containing(TypeId::of::<LivingEntity>())
+        .iter()
+        .map(|extractor| extractor.type_name())
+        .collect();
+    living.sort();
+
+    let mut expected = vec![
+        std::any::type_name::<LivingEntity>(),
+        std::any::type_name::<Player>(),
+        std::any::type_name::<Zombie>(),
+    ];
+    expected.sort();
+    assert_eq!(living, expected);
+
+    let entity = registry::types_containing(TypeId::of::<Entity>());
+    assert_eq!(entity.len(), 5);
+}
+
+/// Test unknown types are not found
+#[test]
+fn test_registry_unknown_type() {
+    assert!(registry::find_type(TypeId::of::<String>()).is_none());
+    assert!(registry::types_containing(TypeId::of::<String>()).is_empty());
+}
+
+/// Test generic instantiations are registered on first use
+#[test]
+fn test_registry_generic_registered_on_use() {
+    struct Marker;
+    let type_id = TypeId::of::<Wrapper<Marker>>();
+    assert!(registry::find_type(type_id).is_none());
+
+    let _wrapper = Acquirable::new(Wrapper { value: Marker });
+
+    let wrapper = registry::find_type(type_id).unwrap();
+    assert_eq!(
+        wrapper.type_name(),
+        std::any::type_name::<Wrapper<Marker>>()
+    );
+    assert!(
+        registry::registered_types()
+            .iter()
+            .any(|extractor| extractor.type_id() == type_id)
+    );
+}
@@ -0,0 +1,213 @@
+#![cfg(all(feature = "serde", feature = "archetype"))]
+#![allow(dead_code)]
+
+use serde::{Deserialize, Serialize};
+use structecs::*;
+
+#[derive(Extractable, Serialize, Deserialize, Debug, PartialEq)]
+#[extractable(tag = "entity")]
+struct Entity {
+    id: u32,
+}
+
+#[derive(Extractable, Serialize, Deserialize, Debug, PartialEq)]
+#[extractable(entity, tag = "player")]
+struct Player {
+    name: String,
+    entity: Entity,
+}
+
+#[derive(Extractable, Serialize, Deserialize, Debug, PartialEq)]
+#[extractable(entity, tag = "zombie")]
+struct Zombie {
+    entity: Entity,
+    damage: u32,
+}
+
+#[derive(Extractable, Serialize, Deserialize, Debug, PartialEq)]
+#[extractable(entity)]
+struct Untagged {
+    entity: Entity,
+}
+
+#[derive(Extractable, Serialize, Deserialize, Debug, PartialEq)]
+#[extractable(tag = "item")]
+struct Item {
+    count: u8,
+}
+
+/// Test Acquirable serializes as its component
+#[test]
+fn test_acquirable_round_trip() {
+    let player = Acquirable::new(Player {
+        name: "Alice".to_string(),
+        entity: Entity { id: 1 },
+    });
+
+    let json = serde_json::to_string(&player).unwrap();
+    assert_eq!(json, r#"{"name":"Alice","entity":{"id":1}}"#);
+
+    let loaded: Acquirable<Player> = serde_json::from_str(&json).unwrap();
+    assert_eq!(*loaded, *player);
+    assert!(!loaded.ptr_eq(&player));
+}
+
+/// Test Archetype entities round-trip as their concrete types
+#[test]
+fn test_archetype_round_trip() {
+    let archetype: Archetype<u32, Entity> = Archetype::default();
+    archetype.insert(
+        1,
+        Player {
+            name: "Alice".to_string(),
+            entity: Entity { id: 1 },
+        },
+    );
+    archetype.insert(
+        2,
+        Zombie {
+            entity: Entity { id: 2 },
+            damage: 4,
+        },
+    );
+    archetype.insert(3, Entity { id: 3 });
+
+    let json = serde_json::to_string(&archetype).unwrap();
+    let loaded: Archetype<u32, Entity> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(loaded.len(), 3);
+
+    let player = loaded.get(&1).unwrap();
+    assert!(player.is::<Player>());
+    assert_eq!(player.extract::<Player>().unwrap().name, "Alice");
+    assert_eq!(player.id, 1);
+
+    let zombie = loaded.get(&2).unwrap();
+    assert!(zombie.is::<Zombie>());
+    assert_eq!(zombie.extract::<Zombie>().unwrap().damage, 4);
+
+    assert!(loaded.get(&3).unwrap().is::<Entity>());
+}
+
+/// Test the serialized form of a tagged entity
+#[test]
+fn test_archetype_serialized_format() {
+    let archetype: Archetype<u32, Entity> = Archetype::default();
+    archetype.insert(
+        7,
+        Zombie {
+            entity: Entity { id: 7 },
+            damage: 1,
+        },
+    );
+
+    let json = serde_json::to_string(&archetype).unwrap();
+    assert_eq!(json, r#"{"7":{"zombie":{"entity":{"id":7},"damage":1}}}"#);
+}
+
+/// Test serializing an entity without a tag fails
+#[test]
+fn test_serialize_untagged_type_fails() {
+    let archetype: Archetype<u32, Entity> = Archetype::default();
+    archetype.insert(
+        1,
+        Untagged {
+            entity: Entity { id: 1 },
+        },
+    );
+
+    let error = serde_json::to_string(&archetype).unwrap_err();
+    assert!(error.to_string().contains("has no serde tag"));
+}
+
+/// Test deserializing an unknown tag fails
+#[test]
+fn test_deserialize_unknown_tag_fails() {
+    let result = serde_json::from_str::<Archetype<u32, Entity>>(r#"{"1":{"skeleton":{}}}"#);
+    let error = result.err().unwrap();
+    assert!(error.to_string().contains("unknown serde tag \"skeleton\""));
+}
+
+/// Test deserializing a tagged type that does not contain the base fails
+#[test]
+fn test_deserialize_type_without_base_fails() {
+    let result = serde_json::from_str::<Archetype<u32, Entity>>(r#"{"1":{"item":{"count":3}}}"#);
+    let error = result.err().unwrap();
+    assert!(error.to_string().contains("does not contain"));
+}
+
+/// Test tagged serialization of a single field
+#[test]
+fn test_tagged_field() {
+    #[derive(Serialize, Deserialize)]
+    struct Save {
+        #[serde(with = "structecs::tagged")]
+        focus: Acquirable<Entity>,
+    }
+
+    let zombie = Acquirable::new(Zombie {
+        entity: Entity { id: 5 },
+        damage: 2,
+    });
+    let save = Save {
+        focus: zombie.extract::<Entity>().unwrap(),
+    };
+
+    let json = serde_json::to_string(&save).unwrap();
+    let loaded: Save = serde_json::from_str(&json).unwrap();
+
+    assert!(loaded.focus.is::<Zombie>());
+    assert_eq!(loaded.focus.id, 5);
+}
+
+/// Test tag lookup through the registry
+#[test]
+fn test_find_tag() {
+    use std::any::TypeId;
+
+    let serde_type = tagged::find_tag("player").unwrap();
+    assert_eq!(serde_type.type_id(), TypeId::of::<Player>());
+    assert_eq!(
+        tagged::find_type(TypeId::of::<Zombie>()).unwrap().tag(),
+        "zombie"
+    );
+    assert!(tagged::find_type(TypeId::of::<Untagged>()).is_none());
+}
+
+/// Test serializing an entity the current thread reads does not wait for a queued writer
+#[cfg(feature = "locking")]
+#[test]
+fn test_serialize_while_read_locked() {
+    #[derive(Serialize, Deserialize)]
+    struct Save {
+        #[serde(with = "structecs::tagged")]
+        focus: Acquirable<Entity>,
+    }
+
+    let player = Acquirable::new_locked(Player {
+        name: "Alice".to_string(),
+        entity: Entity { id: 1 },
+    });
+    let guard = player.read();
+    let writer = std::thread::spawn({
+        let player = player.clone();
+        move || player.write().entity.id = 2
+    });
+    // Give the writer time to queue behind the read guard.
+    std::thread::sleep(std::time::Duration::from_millis(50));
+
+    let json = serde_json::to_string(&player).unwrap();
+    assert_eq!(json, r#"{"name":"Alice","entity":{"id":1}}"#);
+    let save = Save {
+        focus: player.extract::<Entity>().unwrap(),
+    };
+    let json = serde_json::to_string(&save).unwrap();
+    assert_eq!(
+        json,
+        r#"{"focus":{"player":{"name":"Alice","entity":{"id":1}}}}"#
+    );
+
+    drop(guard);
+    writer.join().unwrap();
+    assert_eq!(player.read().entity.id, 2);
+}
@@ -0,0 +1,202 @@
+#![cfg(feature = "archetype")]
+#![allow(dead_code)]
+
+use structecs::*;
+
+#[derive(Extractable, Debug)]
+struct Entity {
+    id: u32,
+}
+
+#[derive(Extractable, Debug)]
+#[extractable(entity)]
+struct Player {
+    name: String,
+    entity: Entity,
+}
+
+fn new_sharded(shards: usize) -> ShardedArchetype<u32, Entity> {
+    let archetype = ShardedArchetype::with_shards(shards);
+    for id in 0..100 {
+        if id % 4 == 0 {
+            archetype.insert(
+                id,
+                Player {
+                    name: format!("Player{id}"),
+                    entity: Entity { id },
+                },
+            );
+        } else {
+            archetype.insert(id, Entity { id });
+        }
+    }
+    archetype
+}
+
+/// Test the shard count is rounded up to a power of two
+#[test]
+fn test_sharded_shard_count() {
+    assert_eq!(
+        ShardedArchetype::<u32, Entity>::with_shards(0).shard_count(),
+        1
+    );
+    assert_eq!(
+        ShardedArchetype::<u32, Entity>::with_shards(5).shard_count(),
+        8
+    );
+    assert_eq!(
+        ShardedArchetype::<u32, Entity>::with_shards(16).shard_count(),
+        16
+    );
+    assert!(
+        ShardedArchetype::<u32, Entity>::default()
+            .shard_count()
+            .is_power_of_two()
+    );
+}
+
+/// Test insert, get and remove across shards
+#[test]
+fn test_sharded_insert_get_remove() {
+    for shards in [1, 2, 16] {
+        let archetype = new_sharded(shards);
+        assert_eq!(archetype.len(), 100);
+
+        for id in 0..100 {
+            assert!(archetype.contains_key(&id));
+            assert_eq!(archetype.get(&id).unwrap().id, id);
+        }
+
+        assert_eq!(archetype.remove(&10).unwrap().id, 10);
+        assert!(archetype.remove(&10).is_none());
+        assert!(archetype.get(&10).is_none());
+        assert_eq!(archetype.len(), 99);
+
+        archetype.clear();
+        assert!(archetype.is_empty());
+    }
+}
+
+/// Test the read guard sees the entries of every shard
+#[test]
+fn test_sharded_read_guard() {
+    let archetype = new_sharded(16);
+    let guard = archetype.read();
+    assert_eq!(guard.len(), 100);
+    assert_eq!(guard.iter().count(), 100);
+    assert_eq!(guard.get(&42).unwrap().id, 42);
+    assert!(!guard.contains_key(&1000));
+}
+
+/// Test iter_as and the query helpers over all shards
+#[test]
+fn test_sharded_queries() {
+    let archetype = new_sharded(8);
+
+    let mut players: Vec<_> = archetype
+        .iter_as::<Player>()
+        .map(|(key, player)| {
+            assert_eq!(player.name, format!("Player{key}"));
+            key
+        })
+        .collect();
+    players.sort();
+    assert_eq!(players, (0..100).step_by(4).collect::<Vec<_>>());
+
+    let mut sum = 0;
+    archetype.for_each(|_, entity| sum += entity.id);
+    assert_eq!(sum, (0..100).sum::<u32>());
+
+    assert_eq!(archetype.filter(|key, _| *key < 10).len(), 10);
+
+    let drained = archetype.drain_filter(|_, entity| entity.is::<Player>());
+    assert_eq!(drained.len(), 25);
+    assert_eq!(archetype.iter_as::<Player>().count(), 0);
+
+    archetype.retain(|key, _| key % 2 == 0);
+    assert_eq!(archetype.len(), 25);
+}
+
+/// Test concurrent inserts and removals from multiple threads
+#[test]
+fn test_sharded_concurrent() {
+    use std::thread;
+
+    let archetype = ShardedArchetype::<u32, Entity>::with_shards(8);
+
+    thread::scope(|scope| {
+        for i in 0..8 {
+            let archetype = archetype.clone();
+            scope.spawn(move || {
+                for j in 0..1000 {
+                    let id = i * 1000 + j;
+                    archetype.insert(id, Entity { id });
+                    if j % 2 == 1 {
+                        archetype.remove(&id);
+                    }
+                }
+            });
+        }
+        scope.spawn(|| {
+            for _ in 0..100 {
+                let guard = archetype.read();
+                assert_eq!(guard.len(), guard.iter().count());
+            }
+        });
+    });
+
+    assert_eq!(archetype.len(), 4000);
+    for (key, entity) in archetype.iter_as::<Entity>() {
+        assert_eq!(key, entity.id);
+        assert_eq!(key % 2, 0);
+    }
+}
+
+/// Test sharded archetypes round-trip through serde
+#[cfg(feature = "serde")]
+#[test]
+fn test_sharded_serde_round_trip() {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Extractable, Serialize, Deserialize)]
+    #[extractable(tag = "sharded_item")]
+    struct Item {
+        count: u32,
+    }
+
+    let archetype = ShardedArchetype::<u32, Item>::with_shards(4);
+    for count in 0..10 {
+        archetype.insert(count, Item { count });
+    }
+
+    let json = serde_json::to_string(&archetype).unwrap();
+    let loaded: ShardedArchetype<u32, Item> = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.len(), 10);
+    for count in 0..10 {
+        assert_eq!(loaded.get(&count).unwrap().count, count);
+    }
+}
+
+/// Test despawn hooks of replaced and cleared entities can modify the archetype
+#[test]
+fn test_sharded_despawn_hook_modifies_archetype() {
+    // A single shard, so both keys are guarded by the same lock
+    let archetype = ShardedArchetype::<u32, Entity>::with_shards(1);
+    let despawn_removes = |key: u32, target: u32| {
+        let archetype_ref = archetype.clone();
+        archetype
+            .insert(key, Entity { id: key })
+            .on_destroy(move |_| drop(archetype_ref.remove(&target)));
+    };
+
+    despawn_removes(1, 2);
+    archetype.insert(2, Entity { id: 2 });
+    archetype.insert(1, Entity { id: 10 });
+    assert!(!archetype.contains_key(&2));
+    assert_eq!(archetype.get(&1).unwrap().id, 10);
+
+    despawn_removes(3, 4);
+    archetype.insert(4, Entity { id: 4 });
+    archetype.clear();
+    assert!(archetype.is_empty());
+}